@@ -0,0 +1,80 @@
+//! This benchmark compares running separate `dnf repoquery` calls for each kind of information
+//! about installed packages (as was done up to version 2.1.4) with the one combined query that is
+//! used by [`DnfInventory`]. It needs `dnf` and a populated RPM database, and is skipped otherwise:
+//!
+//! `cargo bench --bench queries`
+
+#![allow(clippy::unwrap_used)]
+
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use fedora_update_feedback::sysinfo::{parse_dnf_version, DnfInventory, DnfVersion, Inventory};
+
+// number of times each approach is timed
+const RUNS: u32 = 5;
+
+// query formats of the separate queries that were run before
+const SEPARATE_FORMATS: [&str; 4] = [
+    "%{sourcerpm}",
+    "%{name}\t%{summary}",
+    "%{source_name}-%{version}-%{release} %{name}-%{version}-%{release}.%{arch}",
+    "%{name}-%{version}-%{release}.%{arch}\t%{installtime}",
+];
+
+/// This helper function runs `dnf repoquery` for installed packages with the given query format.
+fn repoquery(queryformat: &str, dnf5: bool) {
+    // dnf5 does not terminate query results with newline characters
+    let queryformat = if dnf5 {
+        format!("{}\n", queryformat)
+    } else {
+        queryformat.to_string()
+    };
+
+    let output = Command::new("dnf")
+        .args(["--quiet", "repoquery", "--cacheonly", "--installed", "--queryformat"])
+        .arg(queryformat)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[tokio::main]
+async fn main() {
+    // "cargo test --all-targets" also builds and runs benchmarks, but without the --bench argument
+    if !std::env::args().any(|arg| arg == "--bench") {
+        return;
+    }
+
+    let Ok(version) = Command::new("dnf").arg("--version").output() else {
+        println!("dnf is not available, skipping benchmark.");
+        return;
+    };
+    let dnf5 = parse_dnf_version(&String::from_utf8_lossy(&version.stdout)) == DnfVersion::Dnf5;
+
+    // warm up the page cache, so the first timed run is not slower than the others
+    repoquery(SEPARATE_FORMATS[0], dnf5);
+
+    let mut separate = Duration::ZERO;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        for format in SEPARATE_FORMATS {
+            repoquery(format, dnf5);
+        }
+        separate += start.elapsed();
+    }
+
+    let mut combined = Duration::ZERO;
+    for _ in 0..RUNS {
+        let inventory = DnfInventory::detect().await.unwrap();
+        let start = Instant::now();
+        inventory.get_installed().await.unwrap();
+        inventory.get_summaries().await.unwrap();
+        inventory.get_src_bin_map().await.unwrap();
+        inventory.get_installation_times().await.unwrap();
+        combined += start.elapsed();
+    }
+
+    println!("separate queries: {:?} per run", separate / RUNS);
+    println!("combined query:   {:?} per run", combined / RUNS);
+}
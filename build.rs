@@ -1,4 +1,3 @@
-use clap::CommandFactory;
 use clap_complete::{generate_to, Shell};
 
 include!("src/cli.rs");
@@ -0,0 +1,1346 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bodhi::{BodhiClient, FedoraRelease, Karma, Update};
+use chrono::Utc;
+use tokio::runtime::Handle;
+use tokio::sync::OnceCell;
+use tokio::task::{spawn_blocking, JoinHandle};
+use tokio::time::sleep;
+
+use crate::batch::{batch_feedback, read_batch_file, BatchFile};
+use crate::changelog::{excerpt, ChangelogExcerpt};
+use crate::checks::{
+    comment_templates,
+    do_check_obsoletes,
+    do_check_pending,
+    do_check_running,
+    do_check_unpushed,
+    do_include_system_info,
+    do_skip_partial,
+    do_use_inline_editor,
+    installed_max_days,
+    installed_min_days,
+    obsoleted_check,
+    soak_time_config,
+    unpushed_check,
+};
+use crate::cli::{Action, Check, Command, CommonArgs, IgnoreAction, ListArgs, ListFormat, ReviewArgs};
+use crate::config::{get_config, get_legacy_username, FedoraConfig};
+use crate::error::Error;
+use crate::filter::{
+    apply_ignore_lists,
+    filter_installed_days,
+    filter_staged_updates,
+    install_time,
+    missing_builds,
+    packages_in_update,
+    select_updates,
+};
+use crate::footer::{append_footer, format_footer, SystemSummary};
+use crate::history::{changes_since_last_comment, feedback_status, get_snapshots};
+use crate::ignore::{get_ignored, set_ignored, IgnoreLists};
+use crate::input::{ask_feedback, Feedback, Progress, Prompter};
+use crate::instance::BodhiInstance;
+use crate::list::{format_json, format_table, ListEntry};
+use crate::output::{print_server_messages, proper_plural, QueryProgress, UpdateDetails};
+use crate::parse::parse_nevra;
+use crate::query::{query_obsoleted, query_pending, query_testing, query_unpushed};
+use crate::queue::{get_queued, save_feedback, submit_feedback, submit_queued, QueuedFeedback};
+use crate::restart::{restart_reasons, RestartReason};
+use crate::secrets::{get_store_password, password_from_env, read_password};
+use crate::soak::check_soak_time;
+use crate::summary::{format_summary, SessionSummary};
+use crate::sysinfo::{FixtureInventory, InstalledPackages, Inventory, RunningSystem, SystemInventory};
+use crate::template::{hostname, render_templates, TemplateContext};
+
+const USER_AGENT: &str = concat!("fedora-update-feedback v", env!("CARGO_PKG_VERSION"));
+
+// number of attempts for submitting feedback again that failed during a review session
+const RETRY_ATTEMPTS: u32 = 3;
+
+// delay before the first retry, which is doubled after every attempt
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+// canned information about installed packages that is used instead of querying the system (this is
+// only set by the integration tests, and not part of the command line interface)
+const INVENTORY_FIXTURE_VAR: &str = "FUF_INVENTORY_FIXTURE";
+
+// name of the step that collects information about installed packages in the progress display
+const INVENTORY_PROGRESS: &str = "Installed packages";
+
+/// This struct contains a bodhi client for the selected bodhi instance, and the name of the user
+/// that feedback is provided as.
+#[derive(Debug)]
+pub struct Session {
+    // shared with queries that run in the background
+    bodhi: Arc<BodhiClient>,
+    username: String,
+    instance: BodhiInstance,
+}
+
+impl Session {
+    /// This method returns the bodhi client for this session.
+    pub fn bodhi(&self) -> &BodhiClient {
+        &self.bodhi
+    }
+
+    /// This method returns the FAS username for this session.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// This method returns the bodhi instance that this session is connected to.
+    pub fn instance(&self) -> &BodhiInstance {
+        &self.instance
+    }
+}
+
+/// This struct contains the updates that were selected for review.
+#[derive(Debug)]
+pub struct Selection<'a> {
+    /// installed updates that were not created by the current user, sorted by submission date
+    pub updates: Vec<&'a Update>,
+    /// updates with builds that are only present in a staged deployment
+    pub staged: Vec<&'a Update>,
+    /// map from update aliases to the builds from these updates that are installed
+    pub builds_for_update: HashMap<String, Vec<String>>,
+}
+
+/// This struct contains queries for obsoleted and unpushed updates that run in the background (see
+/// [`App::prefetch_checks`]).
+#[derive(Debug, Default)]
+pub struct PrefetchedChecks {
+    obsoleted: Option<JoinHandle<Result<Vec<Update>, Error>>>,
+    unpushed: Option<JoinHandle<Result<Vec<Update>, Error>>>,
+}
+
+/// This helper function waits for the result of a query that runs in the background.
+async fn prefetched(query: JoinHandle<Result<Vec<Update>, Error>>) -> Result<Vec<Update>, Error> {
+    query
+        .await
+        .map_err(|error| Error::bodhi_with("Failed to query bodhi in the background", error))?
+}
+
+/// This struct represents one run of `fedora-update-feedback`.
+///
+/// The individual phases of a run (loading local state, authenticating, collecting information
+/// about installed packages, selecting updates, reviewing them, running additional checks, and
+/// persisting local state) can be called separately, or all at once for the subcommands that were
+/// specified on the command line with [`App::run`]:
+///
+/// ```no_run
+/// use fedora_update_feedback::app::App;
+/// use fedora_update_feedback::cli::CommonArgs;
+/// use fedora_update_feedback::error::Error;
+///
+/// # async fn example() -> Result<(), Error> {
+/// let app = App::load(CommonArgs::default()).await?;
+/// let session = app.authenticate(true).await?;
+/// let installed = app.collect_inventory().await?;
+/// let updates = app
+///     .query_updates(&session, &installed.release, false)
+///     .await?;
+/// let selection = app.select_updates(&session, &updates, &installed)?;
+///
+/// for update in selection.updates {
+///     println!("{}: {}", update.alias, update.title);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct App {
+    args: CommonArgs,
+    config: Option<FedoraConfig>,
+    ignored: IgnoreLists,
+    // print status messages to stderr instead of stdout, and omit blank lines
+    quiet: bool,
+    // source of information about installed packages, which is kept for later queries
+    inventory: OnceCell<SystemInventory>,
+    // information about the running system, which is only queried once
+    running: OnceCell<RunningSystem>,
+}
+
+impl App {
+    /// This method loads local state: the lists of ignored updates and packages, and the
+    /// configuration file. A missing configuration file is not an error, but one that cannot be
+    /// read or parsed is.
+    pub async fn load(args: CommonArgs) -> Result<App, Error> {
+        let ignored = get_ignored().await.unwrap_or_default();
+        let config = get_config().await?;
+
+        Ok(App {
+            args,
+            config,
+            ignored,
+            quiet: false,
+            inventory: OnceCell::new(),
+            running: OnceCell::new(),
+        })
+    }
+
+    /// This helper method prints a status message. If the output of a subcommand is supposed to
+    /// be machine-readable, status messages are printed to stderr instead.
+    fn notice(&self, message: impl Display) {
+        if self.quiet {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /// This helper method prints an empty line, unless the output of a subcommand is supposed to be
+    /// machine-readable.
+    fn separator(&self) {
+        if !self.quiet {
+            println!();
+        }
+    }
+
+    /// This method returns the command line arguments that are shared by all subcommands.
+    pub fn args(&self) -> &CommonArgs {
+        &self.args
+    }
+
+    /// This method returns the lists of ignored updates and packages.
+    pub fn ignored(&self) -> &IgnoreLists {
+        &self.ignored
+    }
+
+    /// This method clears the lists of ignored updates and packages. The change is only written
+    /// to disk by [`App::persist`].
+    pub fn clear_ignored(&mut self) {
+        self.ignored = IgnoreLists::default();
+    }
+
+    /// This method adds or removes an ignored package, or prints or clears the lists of ignored
+    /// packages and updates.
+    pub async fn manage_ignored(&mut self, action: &IgnoreAction) -> Result<(), Error> {
+        let ignored = &mut self.ignored;
+
+        match action {
+            IgnoreAction::Add { package } => {
+                if !ignored.ignored_packages.contains(package) {
+                    println!("Added '{}' to the list of ignored packages.", &package);
+                    ignored.ignored_packages.push(package.clone());
+                    ignored.ignored_updates.sort();
+                    set_ignored(ignored).await?;
+                } else {
+                    println!("Already in the list of ignored packages: '{}'", &package);
+                };
+            },
+            IgnoreAction::Remove { package } => {
+                if ignored.ignored_packages.contains(package) {
+                    println!("Removed '{}' from the list of ignored packages.", &package);
+                    ignored.ignored_packages.retain(|p| p != package);
+                    set_ignored(ignored).await?;
+                } else {
+                    println!("Not in the list of ignored packages: '{}'", &package);
+                };
+            },
+            IgnoreAction::List => {
+                println!(
+                    "Ignored updates:{}",
+                    if ignored.ignored_updates.is_empty() {
+                        " none"
+                    } else {
+                        ""
+                    }
+                );
+                for update in &ignored.ignored_updates {
+                    println!("- {}", update);
+                }
+                println!();
+
+                println!(
+                    "Ignored packages:{}",
+                    if ignored.ignored_packages.is_empty() {
+                        " none"
+                    } else {
+                        ""
+                    }
+                );
+                for package in &ignored.ignored_packages {
+                    println!("- {}", package);
+                }
+                println!();
+            },
+            IgnoreAction::Clear => {
+                println!("Cleared the lists of ignored packages and updates.");
+                *ignored = IgnoreLists::default();
+                set_ignored(ignored).await?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// This method determines the FAS username from command line arguments, the configuration
+    /// file, or the legacy `~/.fedora.upn` file (in this order).
+    pub async fn username(&self) -> Result<String, Error> {
+        if let Some(username) = &self.args.username {
+            Ok(username.clone())
+        } else if let Some(config) = &self.config {
+            Ok(config.fas.username.clone())
+        } else if let Ok(Some(username)) = get_legacy_username().await {
+            Ok(username)
+        } else {
+            Err(Error::config("Failed to read ~/.config/fedora.toml and ~/.fedora.upn."))
+        }
+    }
+
+    /// This method determines the bodhi instance from command line arguments and the
+    /// configuration file.
+    pub fn instance(&self) -> Result<BodhiInstance, Error> {
+        BodhiInstance::from_args(
+            self.args.bodhi_url.as_deref(),
+            self.args.openid_url.as_deref(),
+            self.config.as_ref(),
+        )
+    }
+
+    /// This method determines the username and the bodhi instance, and authenticates with bodhi.
+    /// If `anonymous` is `true` (for example, if feedback is only saved in the local queue), an
+    /// anonymous session is returned instead.
+    pub async fn authenticate(&self, anonymous: bool) -> Result<Session, Error> {
+        let password = if anonymous { None } else { Some(self.password().await?) };
+        self.login(password).await
+    }
+
+    /// This helper method reads the FAS password from the environment, from the session keyring,
+    /// or by prompting for it (in this order).
+    async fn password(&self) -> Result<String, Error> {
+        if let Some(password) = password_from_env() {
+            return Ok(password);
+        }
+
+        match self.config.as_ref().and_then(|config| config.fuf.as_ref()) {
+            Some(fuf) if fuf.save_password == Some(true) => get_store_password(self.args.ignore_keyring).await,
+            _ => read_password(),
+        }
+    }
+
+    /// This helper method determines the username and the bodhi instance, and logs in with the
+    /// given password. If no password is given, an anonymous session is returned instead.
+    async fn login(&self, password: Option<String>) -> Result<Session, Error> {
+        let args = &self.args;
+
+        let username = self.username().await?;
+
+        if args.verbose {
+            self.notice(format!("Username: {}", &username));
+        }
+
+        let instance = self.instance()?;
+
+        if args.verbose && instance != BodhiInstance::Production {
+            self.notice(format!("Using bodhi instance: {}", instance.url()));
+        }
+
+        let bodhi = match password {
+            // no feedback is submitted, so authentication is not necessary
+            None => {
+                if args.verbose {
+                    self.notice("Skipping authentication ...");
+                }
+                instance
+                    .builder()
+                    .user_agent(USER_AGENT)
+                    .build()
+                    .await
+                    .map_err(|error| Error::bodhi_with("Failed to initialize bodhi client", error))?
+            },
+            Some(password) => {
+                if args.verbose {
+                    self.notice("Authenticating with bodhi ...");
+                }
+                instance
+                    .builder()
+                    .user_agent(USER_AGENT)
+                    .authentication(&username, &password)
+                    .build()
+                    .await
+                    .map_err(|error| Error::bodhi_with("Failed to authenticate with bodhi", error))?
+            },
+        };
+
+        Ok(Session {
+            bodhi: Arc::new(bodhi),
+            username,
+            instance,
+        })
+    }
+
+    /// This method submits all feedback that is waiting in the local queue.
+    pub async fn submit_queued(&self, session: &Session) -> Result<(), Error> {
+        submit_queued(&session.bodhi).await
+    }
+
+    /// This method collects information about installed packages, either from the current system,
+    /// or from canned inventory data (if a file was specified).
+    pub async fn collect_inventory(&self) -> Result<InstalledPackages, Error> {
+        let (testing_enabled, installed) = self.read_inventory(&QueryProgress::hidden()).await?;
+        self.warn_testing_disabled(testing_enabled);
+        Ok(installed)
+    }
+
+    /// This method authenticates with bodhi (see [`App::authenticate`]), collects information
+    /// about installed packages, and queries bodhi for updates (see [`App::query_updates`]), with a
+    /// combined progress display.
+    ///
+    /// The password is asked for first, since the prompt would otherwise block the other steps.
+    /// Querying bodhi only needs the Fedora release of the current system, so it starts as soon as
+    /// the session is authenticated, while installed packages are still being read.
+    pub async fn collect_updates(
+        &self,
+        anonymous: bool,
+        check_pending: bool,
+    ) -> Result<(Session, InstalledPackages, Vec<Update>), Error> {
+        let inventory = self.inventory().await?;
+        let password = if anonymous { None } else { Some(self.password().await?) };
+
+        let progress = QueryProgress::new();
+        let ((testing_enabled, installed), (session, updates)) =
+            tokio::try_join!(self.read_inventory(&progress), async {
+                let (session, release) = tokio::try_join!(self.login(password), inventory.get_release())?;
+                let updates = self
+                    .query_with_progress(&session, &release, check_pending, &progress)
+                    .await?;
+                Ok((session, updates))
+            },)?;
+        self.separator();
+
+        // warnings are only printed after the progress display is finished
+        self.warn_testing_disabled(testing_enabled);
+
+        if self.args.verbose {
+            self.separator();
+        }
+
+        Ok((session, installed, updates))
+    }
+
+    /// This helper method reads information about installed packages and whether the
+    /// "updates-testing" repository is enabled, and shows when it is done in the given progress
+    /// display.
+    async fn read_inventory(&self, progress: &QueryProgress) -> Result<(bool, InstalledPackages), Error> {
+        let inventory = self.inventory().await?;
+
+        // query rpm for the current Fedora release number, and installed packages (all information
+        // is read in one pass), while checking which repositories are enabled
+        if self.args.verbose {
+            self.notice("Querying RPM for the current Fedora release number ...");
+            self.notice("Querying installed packages ...");
+        }
+
+        let result = tokio::try_join!(
+            inventory.is_update_testing_enabled(),
+            InstalledPackages::collect(inventory)
+        )?;
+
+        // there is no meaningful intermediate progress, and showing it from the start would
+        // interfere with status messages that are printed while authenticating
+        progress.update(INVENTORY_PROGRESS, 1, 1);
+
+        Ok(result)
+    }
+
+    /// This helper method prints a warning if the "updates-testing" repository is not enabled.
+    fn warn_testing_disabled(&self, testing_enabled: bool) {
+        if !testing_enabled {
+            self.notice("WARNING: The 'updates-testing' repository does not seem to be enabled.");
+            self.notice("         Usefulness of fedora-update-feedback will be limited.");
+        }
+    }
+
+    /// This helper method selects the source of information about installed packages when it is
+    /// first needed.
+    async fn inventory(&self) -> Result<&SystemInventory, Error> {
+        self.inventory
+            .get_or_try_init(|| async {
+                match std::env::var_os(INVENTORY_FIXTURE_VAR) {
+                    Some(path) => Ok(SystemInventory::Fixture(Box::new(
+                        FixtureInventory::from_file(Path::new(&path)).await?,
+                    ))),
+                    None => {
+                        let inventory = SystemInventory::detect().await?;
+                        for warning in inventory.warnings() {
+                            self.notice(format!("WARNING: {}", warning));
+                        }
+                        Ok(inventory)
+                    },
+                }
+            })
+            .await
+    }
+
+    /// This helper method returns information about the running system, which is only queried
+    /// once per run.
+    async fn running_system(&self) -> Result<&RunningSystem, Error> {
+        self.running
+            .get_or_try_init(|| async { self.inventory().await?.get_running_system().await })
+            .await
+    }
+
+    /// This helper method returns the running kernel and the hardware model. Running processes are
+    /// only inspected if that was already necessary for determining restart reasons, since reading
+    /// this information on its own is much faster.
+    async fn system_details(&self) -> Result<RunningSystem, Error> {
+        match self.running.get() {
+            Some(running) => Ok(running.clone()),
+            None => self.inventory().await?.get_system_details().await,
+        }
+    }
+
+    /// This method determines for the given updates why their installed builds are not in use by
+    /// the running system yet (for example, because the system has not been rebooted since a new
+    /// kernel was installed), keyed by update alias.
+    ///
+    /// Failing to inspect the running system is not fatal, since the results are only
+    /// informational.
+    pub async fn collect_restart_reasons(
+        &self,
+        updates: &[&Update],
+        builds_for_update: &HashMap<String, Vec<String>>,
+        installed: &InstalledPackages,
+    ) -> HashMap<String, Vec<RestartReason>> {
+        let mut reasons = HashMap::new();
+
+        let inventory = match self.inventory().await {
+            Ok(inventory) => inventory,
+            Err(error) => {
+                self.notice(format!(
+                    "WARNING: Failed to inspect the running system: {}",
+                    error.report()
+                ));
+                return reasons;
+            },
+        };
+
+        let running = match self.running_system().await {
+            Ok(running) => running,
+            Err(error) => {
+                self.notice(format!(
+                    "WARNING: Failed to inspect the running system: {}",
+                    error.report()
+                ));
+                return reasons;
+            },
+        };
+
+        // file lists are only needed if running processes use deleted files
+        let files = if running.deleted_files.is_empty() {
+            HashMap::new()
+        } else {
+            let nvras: Vec<String> = updates
+                .iter()
+                .filter_map(|update| builds_for_update.get(&update.alias))
+                .flatten()
+                .filter_map(|build| installed.src_bin_map.get(build))
+                .flatten()
+                .cloned()
+                .collect();
+
+            inventory.get_files(&nvras).await.unwrap_or_else(|error| {
+                self.notice(format!(
+                    "WARNING: Failed to query files in installed packages: {}",
+                    error.report()
+                ));
+                HashMap::new()
+            })
+        };
+
+        for update in updates {
+            let builds = builds_for_update
+                .get(&update.alias)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let update_reasons = restart_reasons(builds, installed, running, &files);
+            if !update_reasons.is_empty() {
+                reasons.insert(update.alias.clone(), update_reasons);
+            }
+        }
+
+        reasons
+    }
+
+    /// This method collects excerpts from the `%changelog` of installed builds from the given
+    /// updates, keyed by source package NVR. Only entries that are new compared to previously
+    /// installed or stable versions of the packages are included.
+    ///
+    /// Failing to read changelogs is not fatal, since they are only informational.
+    pub async fn collect_changelogs(
+        &self,
+        updates: &[&Update],
+        builds_for_update: &HashMap<String, Vec<String>>,
+        installed: &InstalledPackages,
+    ) -> HashMap<String, ChangelogExcerpt> {
+        let mut changelogs = HashMap::new();
+
+        let inventory = match self.inventory().await {
+            Ok(inventory) => inventory,
+            Err(error) => {
+                self.notice(format!("WARNING: Failed to read changelogs: {}", error.report()));
+                return changelogs;
+            },
+        };
+
+        // map from installed source packages to the binary packages that were built from them
+        let mut binaries: HashMap<&str, Vec<&str>> = HashMap::new();
+        for update in updates {
+            for build in builds_for_update.get(&update.alias).into_iter().flatten() {
+                let nvras = installed.src_bin_map.get(build).into_iter().flatten();
+                binaries.insert(build, nvras.map(String::as_str).collect());
+            }
+        }
+
+        let mut names: Vec<String> = binaries
+            .values()
+            .flatten()
+            .filter_map(|nvra| parse_nevra(nvra).ok().map(|(n, _, _, _, _)| n.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let baseline = inventory.get_baseline_versions(&names).await.unwrap_or_else(|error| {
+            self.notice(format!(
+                "WARNING: Failed to query previous package versions: {}",
+                error.report()
+            ));
+            HashMap::new()
+        });
+
+        for (build, nvras) in binaries {
+            // all binary packages from the same source package have the same changelog
+            let Some(first) = nvras.first() else {
+                continue;
+            };
+
+            let entries = match inventory.get_changelog(first).await {
+                Ok(entries) => entries,
+                Err(error) => {
+                    self.notice(format!(
+                        "WARNING: Failed to read changelog of {}: {}",
+                        first,
+                        error.report()
+                    ));
+                    continue;
+                },
+            };
+
+            let versions: Vec<String> = nvras
+                .iter()
+                .filter_map(|nvra| parse_nevra(nvra).ok().map(|(n, _, _, _, _)| n.to_string()))
+                .filter_map(|name| baseline.get(&name))
+                .flatten()
+                .cloned()
+                .collect();
+
+            changelogs.insert(build.to_string(), excerpt(&entries, &versions));
+        }
+
+        changelogs
+    }
+
+    /// This method queries bodhi for updates in "testing" state (and updates in "pending" state,
+    /// if enabled with `check_pending` or in the configuration file) for the Fedora release of the
+    /// current system.
+    pub async fn query_updates(
+        &self,
+        session: &Session,
+        release: &FedoraRelease,
+        check_pending: bool,
+    ) -> Result<Vec<Update>, Error> {
+        let updates = self
+            .query_with_progress(session, release, check_pending, &QueryProgress::new())
+            .await?;
+        self.separator();
+
+        if self.args.verbose {
+            self.separator();
+        }
+
+        Ok(updates)
+    }
+
+    /// This helper method queries bodhi for updates (see [`App::query_updates`]), and shows the
+    /// progress of the queries in the given progress display.
+    async fn query_with_progress(
+        &self,
+        session: &Session,
+        release: &FedoraRelease,
+        check_pending: bool,
+        progress: &QueryProgress,
+    ) -> Result<Vec<Update>, Error> {
+        let check_pending = do_check_pending(check_pending, self.config.as_ref());
+
+        if self.args.verbose {
+            self.notice("Querying bodhi for 'testing' updates ...");
+            if check_pending {
+                self.notice("Querying bodhi for 'pending' updates ...");
+            }
+        }
+
+        // get updates in "testing" and "pending" state concurrently
+        let (testing_updates, pending_updates) =
+            tokio::try_join!(query_testing(&session.bodhi, release.clone(), progress), async {
+                if check_pending {
+                    query_pending(&session.bodhi, release.clone(), progress).await
+                } else {
+                    Ok(Vec::new())
+                }
+            },)?;
+
+        let mut updates: Vec<Update> = Vec::new();
+        updates.extend(testing_updates);
+        updates.extend(pending_updates);
+
+        Ok(updates)
+    }
+
+    /// This method selects installed updates that were not created by the current user, and
+    /// reports updates that will only be available after the next reboot.
+    pub fn select_updates<'a>(
+        &self,
+        session: &Session,
+        updates: &'a [Update],
+        installed: &InstalledPackages,
+    ) -> Result<Selection<'a>, Error> {
+        // filter out updates for packages that are not installed or that were created by the current user;
+        // and remember which builds are installed for which update
+        let mut builds_for_update: HashMap<String, Vec<String>> = HashMap::new();
+        let selected = select_updates(updates, &session.username, &installed.packages, &mut builds_for_update)?;
+
+        // updates that are only present in a staged deployment cannot be tested yet
+        let staged = filter_staged_updates(updates, &installed.packages, &installed.staged)?;
+        if !staged.is_empty() {
+            self.notice(format!(
+                "Skipping {} which will only be available after the next reboot:",
+                proper_plural(staged.len() as i64, "update")
+            ));
+            for update in &staged {
+                self.notice(format!("- {}: {}", update.alias, update.title));
+            }
+            self.separator();
+        }
+
+        Ok(Selection {
+            updates: selected,
+            staged,
+            builds_for_update,
+        })
+    }
+
+    /// This method submits feedback that was read from a file without prompting.
+    pub async fn submit_batch(
+        &self,
+        session: &Session,
+        selection: &Selection<'_>,
+        batch: &BatchFile,
+        args: &ReviewArgs,
+    ) -> Result<(), Error> {
+        batch_feedback(
+            &session.bodhi,
+            batch,
+            &selection.updates,
+            &session.username,
+            args.check_commented,
+            args.queue,
+        )
+        .await
+    }
+
+    /// This method interactively asks for feedback for all selected updates that were not ignored
+    /// and not already commented on.
+    pub async fn review(
+        &mut self,
+        session: &Session,
+        selection: &Selection<'_>,
+        installed: &InstalledPackages,
+        args: &ReviewArgs,
+    ) -> Result<(), Error> {
+        let mut installed_updates = selection.updates.clone();
+        apply_ignore_lists(&mut installed_updates, &mut self.ignored, args.check_ignored);
+
+        // determine which builds from every update are not installed
+        let mut missing = HashMap::new();
+        for update in &installed_updates {
+            missing.insert(update.alias.as_str(), missing_builds(update, &installed.packages)?);
+        }
+
+        // updates where only some builds are installed cannot be tested completely
+        if do_skip_partial(args.skip_partial, self.config.as_ref()) {
+            let before = installed_updates.len();
+            installed_updates.retain(|update| missing.get(update.alias.as_str()).map_or(true, Vec::is_empty));
+
+            let skipped = before - installed_updates.len();
+            if skipped > 0 {
+                println!(
+                    "Skipping {} where only some builds are installed.",
+                    proper_plural(skipped as i64, "update")
+                );
+                println!();
+            }
+        }
+
+        // updates can be selected by the time that has passed since they were installed
+        let min_days = installed_min_days(args.installed_min_days, self.config.as_ref());
+        let max_days = installed_max_days(args.installed_max_days, self.config.as_ref());
+        if min_days.is_some() || max_days.is_some() {
+            let skipped = filter_installed_days(
+                &mut installed_updates,
+                &selection.builds_for_update,
+                installed,
+                min_days,
+                max_days,
+                Utc::now(),
+            );
+            if skipped > 0 {
+                println!(
+                    "Skipping {} outside of the selected range of installation times.",
+                    proper_plural(skipped as i64, "update")
+                );
+                println!();
+            }
+        }
+
+        // keep track of the number of installed relevant updates
+        let total_updates = installed_updates.len();
+
+        // state of updates at the time feedback was last submitted for them
+        let snapshots = get_snapshots().await.unwrap_or_default();
+
+        // changelogs are only read for updates that are not skipped
+        let shown: Vec<&Update> = installed_updates
+            .iter()
+            .filter(|update| args.check_commented || !feedback_status(update, &session.username).is_current())
+            .copied()
+            .collect();
+        let changelogs = self
+            .collect_changelogs(&shown, &selection.builds_for_update, installed)
+            .await;
+        // inspecting running processes can be slow, so it can be disabled
+        let restart = if do_check_running(args.skip_running_check, self.config.as_ref()) {
+            self.collect_restart_reasons(&shown, &selection.builds_for_update, installed)
+                .await
+        } else {
+            HashMap::new()
+        };
+
+        // host name for comment templates
+        let host = hostname().await;
+
+        // description of the current system that is appended to comments, if enabled
+        let system = if do_include_system_info(args.system_info, self.config.as_ref()) {
+            let running = self.system_details().await.unwrap_or_else(|error| {
+                self.notice(format!(
+                    "WARNING: Failed to inspect the running system: {}",
+                    error.report()
+                ));
+                RunningSystem::default()
+            });
+            Some(SystemSummary::new(installed, &running))
+        } else {
+            None
+        };
+
+        // comments are either written in an external editor or in the terminal
+        let mut prompter = Prompter::new(do_use_inline_editor(args.inline_editor, self.config.as_ref()))?;
+
+        // keep track of what happened to every update, and of feedback that could not be submitted
+        let mut summary = SessionSummary::default();
+        let mut failed: Vec<QueuedFeedback> = Vec::new();
+
+        // iterate over the list of installed relevant updates
+        for (update_number, update) in installed_updates.into_iter().enumerate() {
+            let status = feedback_status(update, &session.username);
+            let prev_ignored = self.ignored.ignored_updates.contains(&update.alias);
+
+            // skip updates where previously provided karma still applies to the current builds
+            if !args.check_commented && status.is_current() {
+                continue;
+            }
+
+            let changes = changes_since_last_comment(update, &session.username, snapshots.get(&update.alias));
+            let progress = Progress::new(update_number, total_updates, status, changes, prev_ignored);
+
+            // this unwrap is safe since we definitely inserted a value for every update earlier
+            #[allow(clippy::unwrap_used)]
+            let builds = selection.builds_for_update.get(update.alias.as_str()).unwrap();
+
+            let mut binaries: Vec<&str> = Vec::new();
+            for build in builds {
+                if let Some(list) = installed.src_bin_map.get(build) {
+                    binaries.extend(list.iter().map(|s| s.as_str()));
+                };
+            }
+
+            let installed_at = install_time(builds, installed);
+            let soak = soak_time_config(self.config.as_ref())
+                .and_then(|config| check_soak_time(config, update, installed_at, Utc::now()));
+
+            let templates = match comment_templates(self.config.as_ref()) {
+                Some(templates) if !templates.is_empty() => {
+                    let context = TemplateContext {
+                        alias: update.alias.clone(),
+                        builds: builds.clone(),
+                        release: installed.release.to_string(),
+                        installed_for: installed_at.map(|installed_at| Utc::now() - installed_at),
+                        hostname: host.clone(),
+                    };
+                    render_templates(templates, &context)
+                },
+                _ => Vec::new(),
+            };
+
+            let update_changelogs: Vec<(&str, &ChangelogExcerpt)> = builds
+                .iter()
+                .filter_map(|build| changelogs.get(build).map(|changelog| (build.as_str(), changelog)))
+                .collect();
+
+            let feedback = ask_feedback(
+                &mut prompter,
+                update,
+                &session.instance,
+                progress,
+                &UpdateDetails {
+                    binaries: &binaries,
+                    installed,
+                    changelogs: &update_changelogs,
+                    missing: missing.get(update.alias.as_str()).map_or(&[], Vec::as_slice),
+                    restart: restart.get(&update.alias).map_or(&[], Vec::as_slice),
+                    soak: soak.as_ref(),
+                },
+                &templates,
+            )
+            .await?;
+
+            if !matches!(feedback, Feedback::Abort) {
+                summary.reviewed += 1;
+            }
+
+            match feedback {
+                Feedback::Abort => {
+                    println!("Aborting.");
+                    println!();
+                    break;
+                },
+                Feedback::Ignore => {
+                    println!("Ignoring.");
+                    println!();
+                    summary.ignored += 1;
+                    self.ignored.ignored_updates.push(update.alias.clone());
+                    self.ignored.ignored_updates.sort();
+                    continue;
+                },
+                Feedback::Block => {
+                    println!("Permanently ignoring all packages from this update.");
+                    println!();
+                    summary.blocked += 1;
+                    let names = packages_in_update(update);
+                    self.ignored.ignored_packages.extend(names);
+                    self.ignored.ignored_packages.sort();
+                    continue;
+                },
+                Feedback::Skip => {
+                    println!("Skipping.");
+                    println!();
+                    summary.skipped += 1;
+                    continue;
+                },
+                Feedback::Values {
+                    comment,
+                    karma,
+                    bug_feedback,
+                    testcase_feedback,
+                } => {
+                    if comment.is_none()
+                        && karma == Karma::Neutral
+                        && bug_feedback.is_empty()
+                        && testcase_feedback.is_empty()
+                    {
+                        println!("Provided neither a comment nor any feedback, skipping update.");
+                        summary.skipped += 1;
+                        continue;
+                    };
+
+                    let comment = match &system {
+                        Some(system) => append_footer(comment, &format_footer(system, &binaries)),
+                        None => comment,
+                    };
+
+                    let feedback = QueuedFeedback::new(update, comment, karma, bug_feedback, testcase_feedback);
+
+                    // only save feedback in the local queue if requested
+                    if args.queue {
+                        save_feedback(feedback).await;
+                        summary.queued += 1;
+                        continue;
+                    }
+
+                    match submit_feedback(&session.bodhi, &feedback).await {
+                        Ok(value) => {
+                            println!("Comment created.");
+                            print_server_messages(&value.caveats);
+                            summary.record_posted(&update.alias, karma, &value.caveats);
+                        },
+                        Err(error) => {
+                            println!("Failed to submit feedback: {}", error);
+                            println!("Submitting it will be retried at the end of this session.");
+                            // do not lose feedback if submitting it failed
+                            failed.push(feedback);
+                        },
+                    };
+                },
+            };
+        }
+
+        self.retry_failed(session, &mut prompter, failed, &mut summary).await;
+
+        if summary.reviewed > 0 {
+            println!();
+            println!("{}", format_summary(&summary));
+        }
+
+        Ok(())
+    }
+
+    /// This method offers to submit feedback again that could not be submitted during a review
+    /// session, with increasing delays between attempts. Feedback that still cannot be submitted
+    /// is saved in the local queue.
+    async fn retry_failed(
+        &self,
+        session: &Session,
+        prompter: &mut Prompter,
+        mut failed: Vec<QueuedFeedback>,
+        summary: &mut SessionSummary,
+    ) {
+        if failed.is_empty() {
+            return;
+        }
+
+        println!();
+        println!(
+            "Submitting feedback failed for {}.",
+            proper_plural(failed.len() as i64, "update")
+        );
+
+        // feedback must not be lost if standard input has been closed in the meantime
+        let retry = prompter
+            .input("Retry submitting feedback? [Y/n]", &["y", "n"])
+            .map(|answer| answer.to_lowercase() != "n")
+            .unwrap_or(false);
+
+        let mut delay = RETRY_DELAY;
+        for attempt in 1..=RETRY_ATTEMPTS {
+            if !retry || failed.is_empty() {
+                break;
+            }
+
+            println!(
+                "Retrying in {} (attempt {} of {}) ...",
+                proper_plural(delay.as_secs() as i64, "second"),
+                attempt,
+                RETRY_ATTEMPTS
+            );
+            sleep(delay).await;
+
+            let mut remaining = Vec::new();
+            for feedback in failed {
+                match submit_feedback(&session.bodhi, &feedback).await {
+                    Ok(value) => {
+                        println!("{}: Comment created.", &feedback.alias);
+                        print_server_messages(&value.caveats);
+                        summary.record_posted(&feedback.alias, feedback.karma, &value.caveats);
+                    },
+                    Err(error) => {
+                        println!("{}: Failed to submit feedback: {}", &feedback.alias, error);
+                        remaining.push(feedback);
+                    },
+                }
+            }
+
+            failed = remaining;
+            delay *= 2;
+        }
+
+        summary.failed = failed.len();
+        for feedback in failed {
+            save_feedback(feedback).await;
+        }
+    }
+
+    /// This method starts queries for obsoleted or unpushed updates (if enabled with
+    /// `check_obsoleted` and `check_unpushed`, or in the configuration file) in the background, so
+    /// their results are already available when updates have been reviewed.
+    pub fn prefetch_checks(
+        &self,
+        session: &Session,
+        installed: &InstalledPackages,
+        check_obsoleted: bool,
+        check_unpushed: bool,
+    ) -> PrefetchedChecks {
+        let prefetch = |unpushed: bool| {
+            let bodhi = Arc::clone(&session.bodhi);
+            let release = installed.release.clone();
+
+            // queries are not `Send`, so they are driven on a blocking thread instead of a task,
+            // and they must not draw progress bars while updates are reviewed
+            spawn_blocking(move || {
+                let progress = QueryProgress::hidden();
+                Handle::current().block_on(async {
+                    if unpushed {
+                        query_unpushed(&bodhi, release, &progress).await
+                    } else {
+                        query_obsoleted(&bodhi, release, &progress).await
+                    }
+                })
+            })
+        };
+
+        PrefetchedChecks {
+            obsoleted: do_check_obsoletes(check_obsoleted, self.config.as_ref()).then(|| prefetch(false)),
+            unpushed: do_check_unpushed(check_unpushed, self.config.as_ref()).then(|| prefetch(true)),
+        }
+    }
+
+    /// This method runs additional checks for installed obsoleted or unpushed updates with the
+    /// results of queries that were started with [`App::prefetch_checks`].
+    pub async fn run_checks(
+        &self,
+        installed: &InstalledPackages,
+        selection: &mut Selection<'_>,
+        checks: PrefetchedChecks,
+    ) -> Result<(), Error> {
+        if let Some(query) = checks.obsoleted {
+            let obsoleted_updates = prefetched(query).await?;
+            obsoleted_check(
+                &obsoleted_updates,
+                &installed.packages,
+                &installed.src_bin_map,
+                &mut selection.builds_for_update,
+            )?;
+        };
+
+        if let Some(query) = checks.unpushed {
+            let unpushed_updates = prefetched(query).await?;
+            unpushed_check(
+                &unpushed_updates,
+                &installed.packages,
+                &installed.src_bin_map,
+                &mut selection.builds_for_update,
+            )?;
+        };
+
+        Ok(())
+    }
+
+    /// This method writes the lists of ignored updates and packages to disk, and reminds the user
+    /// of feedback that is still waiting in the local queue.
+    pub async fn persist(&self) -> Result<(), Error> {
+        // update list of ignored updates
+        if let Err(error) = set_ignored(&self.ignored).await {
+            println!("Failed to write ignored updates to disk.");
+            println!("{}", error);
+        };
+
+        // remind the user of feedback that has not been submitted yet
+        if let Ok(queue) = get_queued().await {
+            if !queue.entries.is_empty() {
+                println!();
+                println!(
+                    "Feedback waiting in the local queue: {}",
+                    proper_plural(queue.entries.len() as i64, "update")
+                );
+                println!("Use the \"submit\" subcommand to submit it.");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This method prints information about the configuration and local state.
+    pub async fn status(&self) -> Result<(), Error> {
+        let username = match self.username().await {
+            Ok(username) => username,
+            Err(_) => String::from("(unknown)"),
+        };
+
+        let queue = get_queued().await?;
+
+        println!("Username:         {}", username);
+        println!("Bodhi instance:   {}", self.instance()?.url());
+        println!(
+            "Configuration:    {}",
+            if self.config.is_some() {
+                "~/.config/fedora.toml"
+            } else {
+                "(none)"
+            }
+        );
+        println!(
+            "Queued feedback:  {}",
+            proper_plural(queue.entries.len() as i64, "update")
+        );
+        println!(
+            "Ignored updates:  {}",
+            proper_plural(self.ignored.ignored_updates.len() as i64, "update")
+        );
+        println!(
+            "Ignored packages: {}",
+            proper_plural(self.ignored.ignored_packages.len() as i64, "package")
+        );
+
+        Ok(())
+    }
+
+    /// This method runs the given subcommand.
+    pub async fn dispatch(&mut self, action: Action) -> Result<(), Error> {
+        match action {
+            Action::Review(args) => self.run_review(&args).await,
+            Action::List(args) => self.run_list(&args).await,
+            Action::Ignore { action } => self.manage_ignored(&action).await,
+            Action::Check { check } => self.run_check(check).await,
+            Action::Submit => {
+                let session = self.authenticate(false).await?;
+                self.submit_queued(&session).await
+            },
+            Action::Status => self.status().await,
+        }
+    }
+
+    /// This method runs all subcommands that were specified on the command line.
+    pub async fn run(args: Command) -> Result<(), Error> {
+        let actions = args.actions();
+        let mut app = App::load(args.common).await?;
+
+        for action in actions {
+            app.dispatch(action).await?;
+        }
+
+        Ok(())
+    }
+
+    /// This method runs all phases for reviewing installed updates in order.
+    async fn run_review(&mut self, args: &ReviewArgs) -> Result<(), Error> {
+        if args.clear_ignored {
+            self.clear_ignored();
+        }
+
+        // read feedback for non-interactive mode before doing anything else
+        let batch = match &args.batch {
+            Some(path) => Some(read_batch_file(path).await?),
+            None => None,
+        };
+
+        // information about installed packages is collected while authenticating with bodhi and
+        // querying updates (if feedback is only saved in the local queue, authentication is not
+        // necessary)
+        let (session, installed, updates) = self.collect_updates(args.queue, args.check_pending).await?;
+        let mut selection = self.select_updates(&session, &updates, &installed)?;
+
+        // submit feedback from file without prompting
+        if let Some(batch) = &batch {
+            return self.submit_batch(&session, &selection, batch, args).await;
+        }
+
+        // additional checks run in the background while updates are reviewed
+        let checks = self.prefetch_checks(&session, &installed, args.check_obsoleted, args.check_unpushed);
+
+        if selection.updates.is_empty() {
+            println!("No updates that are waiting for feedback are currently installed.");
+        } else {
+            self.review(&session, &selection, &installed, args).await?;
+            self.persist().await?;
+        }
+
+        self.run_checks(&installed, &mut selection, checks).await
+    }
+
+    /// This method lists installed updates that are waiting for feedback without prompting.
+    async fn run_list(&mut self, args: &ListArgs) -> Result<(), Error> {
+        // keep stdout machine-readable
+        self.quiet = args.format == ListFormat::Json;
+
+        let (session, installed, updates) = self.collect_updates(true, args.check_pending).await?;
+        let selection = self.select_updates(&session, &updates, &installed)?;
+
+        // the list of ignored updates is not modified when only listing updates
+        let mut ignored = self.ignored.clone();
+        let mut listed = selection.updates.clone();
+        apply_ignore_lists(&mut listed, &mut ignored, args.check_ignored);
+
+        if !args.check_commented {
+            listed.retain(|update| !feedback_status(update, &session.username).is_current());
+        }
+
+        let entries: Vec<ListEntry> = listed
+            .iter()
+            .map(|update| {
+                let builds = selection
+                    .builds_for_update
+                    .get(&update.alias)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                let is_ignored = ignored.ignored_updates.contains(&update.alias);
+                ListEntry::new(
+                    update,
+                    &session.username,
+                    &session.instance,
+                    builds,
+                    &installed,
+                    is_ignored,
+                )
+            })
+            .collect();
+
+        match args.format {
+            ListFormat::Table if entries.is_empty() => {
+                println!("No updates that are waiting for feedback are currently installed.");
+            },
+            ListFormat::Table => println!("{}", format_table(&entries, Utc::now())),
+            ListFormat::Json => println!("{}", format_json(&entries)?),
+        }
+
+        Ok(())
+    }
+
+    /// This method checks for installed obsoleted or unpushed updates.
+    async fn run_check(&self, check: Check) -> Result<(), Error> {
+        let session = self.authenticate(true).await?;
+
+        let installed = self.collect_inventory().await?;
+        let mut builds_for_update: HashMap<String, Vec<String>> = HashMap::new();
+
+        match check {
+            Check::Obsoleted => {
+                let obsoleted_updates =
+                    query_obsoleted(&session.bodhi, installed.release.clone(), &QueryProgress::new()).await?;
+                println!();
+
+                obsoleted_check(
+                    &obsoleted_updates,
+                    &installed.packages,
+                    &installed.src_bin_map,
+                    &mut builds_for_update,
+                )
+            },
+            Check::Unpushed => {
+                let unpushed_updates =
+                    query_unpushed(&session.bodhi, installed.release.clone(), &QueryProgress::new()).await?;
+                println!();
+
+                unpushed_check(
+                    &unpushed_updates,
+                    &installed.packages,
+                    &installed.src_bin_map,
+                    &mut builds_for_update,
+                )
+            },
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use bodhi::{BodhiClient, Karma, Update};
+use serde::Deserialize;
+use tokio::fs::read_to_string;
+
+use crate::error::Error;
+use crate::filter::packages_in_update;
+use crate::history::feedback_status;
+use crate::output::{print_server_messages, proper_plural};
+use crate::queue::{save_feedback, submit_feedback, QueuedFeedback};
+
+/// This struct represents the contents of a file with feedback for multiple updates, which can be
+/// submitted without any interactive prompts. Files can be written either in TOML or JSON format
+/// (if the file name ends with `.json`). A TOML file should look something like this:
+///
+/// ```toml
+/// [[feedback]]
+/// update = "FEDORA-2022-1234567890"
+/// comment = "Works fine."
+/// karma = 1
+///
+/// [[feedback]]
+/// package = "dnf"
+/// karma = 1
+/// bugs = { "1234567" = 1 }
+/// test-cases = { "QA:Testcase_dnf_install" = 1 }
+/// ```
+///
+/// Every entry needs to specify either an update alias or a (source) package name. Package names
+/// match all installed updates that contain a build of this package.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchFile {
+    /// list of feedback items
+    pub feedback: Vec<BatchEntry>,
+}
+
+/// This struct represents feedback for a single update (or package) in a [`BatchFile`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchEntry {
+    /// alias of the update
+    pub update: Option<String>,
+    /// name of a source package
+    pub package: Option<String>,
+    /// comment text (can be multiple lines)
+    pub comment: Option<String>,
+    /// feedback karma (+1, 0, -1)
+    #[serde(default)]
+    pub karma: Karma,
+    /// map from bug IDs to bug feedback karma
+    #[serde(default)]
+    pub bugs: BTreeMap<String, Karma>,
+    /// map from test case names to test case feedback karma
+    #[serde(default, rename = "test-cases")]
+    pub test_cases: BTreeMap<String, Karma>,
+}
+
+impl BatchEntry {
+    fn target(&self) -> String {
+        match (&self.update, &self.package) {
+            (Some(alias), _) => alias.to_string(),
+            (None, Some(package)) => format!("package '{}'", package),
+            (None, None) => String::from("(None)"),
+        }
+    }
+
+    fn matches(&self, update: &Update) -> bool {
+        match (&self.update, &self.package) {
+            (Some(alias), _) => &update.alias == alias,
+            (None, Some(package)) => packages_in_update(update).contains(package),
+            (None, None) => false,
+        }
+    }
+
+    fn bug_feedback(&self) -> Result<Vec<(u32, Karma)>, Error> {
+        self.bugs
+            .iter()
+            .map(|(id, karma)| match id.parse::<u32>() {
+                Ok(id) => Ok((id, *karma)),
+                Err(error) => Err(Error::parse_with(
+                    format!("Invalid bug ID for {}: {}", self.target(), id),
+                    error,
+                )),
+            })
+            .collect()
+    }
+
+    fn testcase_feedback(&self) -> Vec<(&str, Karma)> {
+        self.test_cases
+            .iter()
+            .map(|(name, karma)| (name.as_str(), *karma))
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        match (&self.update, &self.package) {
+            (Some(_), Some(_)) => {
+                return Err(Error::parse(format!(
+                    "Feedback for {} specifies both an update and a package.",
+                    self.target()
+                )))
+            },
+            (None, None) => return Err(Error::parse("Feedback needs to specify either an update or a package.")),
+            _ => {},
+        };
+
+        if self.comment.is_none() && self.karma == Karma::Neutral && self.bugs.is_empty() && self.test_cases.is_empty()
+        {
+            return Err(Error::parse(format!(
+                "Feedback for {} contains neither a comment nor any feedback.",
+                self.target()
+            )));
+        }
+
+        self.bug_feedback()?;
+        Ok(())
+    }
+}
+
+/// This helper function reads, parses, and validates a file with feedback for multiple updates.
+pub async fn read_batch_file(path: &Path) -> Result<BatchFile, Error> {
+    let contents = read_to_string(path)
+        .await
+        .map_err(|error| Error::io(format!("Unable to read {}", path.display()), error))?;
+
+    let batch: BatchFile = match path.extension() {
+        Some(extension) if extension == "json" => serde_json::from_str(&contents)
+            .map_err(|error| Error::parse_with(format!("Unable to parse {}", path.display()), error))?,
+        _ => toml::from_str(&contents)
+            .map_err(|error| Error::parse_with(format!("Unable to parse {}", path.display()), error))?,
+    };
+
+    for entry in &batch.feedback {
+        entry.validate()?;
+    }
+
+    Ok(batch)
+}
+
+/// This helper function submits feedback for all updates in a [`BatchFile`] without prompting
+/// for any input. Only installed updates are considered, and updates that were already commented
+/// on are skipped unless `check_commented` is `true`.
+///
+/// If `queue` is `true`, feedback is only saved in the local queue instead of being submitted.
+pub async fn batch_feedback(
+    bodhi: &BodhiClient,
+    batch: &BatchFile,
+    installed_updates: &[&Update],
+    username: &str,
+    check_commented: bool,
+    queue: bool,
+) -> Result<(), Error> {
+    let mut submitted = 0;
+    let mut skipped = 0;
+    let mut failed = 0;
+    let mut unmatched = 0;
+
+    for entry in &batch.feedback {
+        let matched: Vec<&Update> = installed_updates
+            .iter()
+            .filter(|update| entry.matches(update))
+            .copied()
+            .collect();
+
+        if matched.is_empty() {
+            println!("{}: No matching update is installed.", entry.target());
+            unmatched += 1;
+            continue;
+        }
+
+        for update in matched {
+            if !check_commented && feedback_status(update, username).is_current() {
+                println!("{}: A comment was already submitted, skipping.", &update.alias);
+                skipped += 1;
+                continue;
+            }
+
+            let feedback = QueuedFeedback::new(
+                update,
+                entry.comment.clone(),
+                entry.karma,
+                entry.bug_feedback()?,
+                entry.testcase_feedback(),
+            );
+
+            if queue {
+                println!("{}:", &update.alias);
+                save_feedback(feedback).await;
+                submitted += 1;
+                continue;
+            }
+
+            match submit_feedback(bodhi, &feedback).await {
+                Ok(value) => {
+                    println!("{}: Comment created.", &update.alias);
+                    print_server_messages(&value.caveats);
+                    submitted += 1;
+                },
+                Err(error) => {
+                    println!("{}: {}", &update.alias, error);
+                    save_feedback(feedback).await;
+                    failed += 1;
+                },
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{}: {}, Skipped: {}, Failed: {}",
+        if queue { "Queued" } else { "Submitted" },
+        submitted,
+        skipped,
+        failed
+    );
+
+    if unmatched != 0 {
+        println!("Not installed: {}", unmatched);
+    }
+
+    // feedback for updates that are not installed is a problem with the batch file, not with bodhi
+    if failed != 0 {
+        Err(Error::submit(format!(
+            "Failed to provide {}.",
+            proper_plural(failed as i64, "feedback item")
+        )))
+    } else if unmatched != 0 {
+        Err(Error::parse(format!(
+            "No installed update matches {}.",
+            proper_plural(unmatched as i64, "feedback item")
+        )))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+// maximum number of changelog entries that are shown for a single build
+const MAX_ENTRIES: usize = 10;
+
+/// This struct represents one entry from the `%changelog` of an RPM package.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChangelogEntry {
+    /// header line of the entry (date, author, and usually the version), without the leading `*`
+    pub header: String,
+    /// text of the entry (usually a list of changes)
+    pub text: String,
+}
+
+impl ChangelogEntry {
+    /// This method returns the `VERSION-RELEASE` string from the header of this entry, if present.
+    ///
+    /// Headers conventionally end with the version of the package, like
+    /// `Mon May 06 2024 Jane Doe <jane@example.com> - 1.2-1`, but the version is optional.
+    pub fn version(&self) -> Option<&str> {
+        let last = self.header.split_whitespace().last()?;
+
+        // e-mail addresses or names at the end of the header are not versions
+        if last.ends_with('>') || !last.contains('-') || !last.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+
+        // strip epoch, if any
+        Some(last.split_once(':').map_or(last, |(_, vr)| vr))
+    }
+}
+
+/// This struct contains the changelog entries of a build that are new compared to a baseline
+/// version.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChangelogExcerpt {
+    /// new changelog entries, newest first
+    pub entries: Vec<ChangelogEntry>,
+    /// baseline version that the changelog was compared against (`None` if no baseline version was
+    /// found in the changelog, in which case only the most recent entry is included)
+    pub since: Option<String>,
+    /// whether further new entries were omitted
+    pub truncated: bool,
+}
+
+/// This helper function removes the dist tag (for example, `.fc40`) from an RPM release string,
+/// since changelog headers usually do not include it.
+fn strip_dist(release: &str) -> &str {
+    match release.rfind(".fc") {
+        Some(index) if release[index + 3..].chars().all(|c| c.is_ascii_digit()) => &release[..index],
+        _ => release,
+    }
+}
+
+/// This helper function parses the output of `rpm --query --changelog`.
+///
+/// Every entry starts with a line that starts with `* `, and entries are separated by empty lines.
+pub fn parse_changelog(output: &str) -> Vec<ChangelogEntry> {
+    let mut entries: Vec<ChangelogEntry> = Vec::new();
+    let mut previous_empty = true;
+
+    for line in output.lines() {
+        match line.strip_prefix("* ") {
+            Some(header) if previous_empty => entries.push(ChangelogEntry {
+                header: header.trim().to_string(),
+                text: String::new(),
+            }),
+            _ => {
+                if let Some(entry) = entries.last_mut() {
+                    entry.text.push_str(line);
+                    entry.text.push('\n');
+                }
+            },
+        }
+
+        previous_empty = line.trim().is_empty();
+    }
+
+    for entry in &mut entries {
+        entry.text = entry.text.trim_end().to_string();
+    }
+
+    entries
+}
+
+/// This function selects the changelog entries that were added after one of the baseline versions
+/// (as `VERSION-RELEASE` strings, with or without dist tag).
+///
+/// If none of the baseline versions occur in the changelog (for example, if no baseline version is
+/// known, or if the package is new), only the most recent entry is included.
+pub fn excerpt(entries: &[ChangelogEntry], baseline: &[String]) -> ChangelogExcerpt {
+    let baseline: Vec<&str> = baseline.iter().map(|version| strip_dist(version)).collect();
+
+    let position = entries.iter().position(|entry| match entry.version() {
+        Some(version) => baseline.contains(&strip_dist(version)),
+        None => false,
+    });
+
+    let (new, since) = match position {
+        Some(position) => (&entries[..position], entries[position].version().map(String::from)),
+        None => (&entries[..entries.len().min(1)], None),
+    };
+
+    ChangelogExcerpt {
+        entries: new.iter().take(MAX_ENTRIES).cloned().collect(),
+        since,
+        truncated: new.len() > MAX_ENTRIES,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const CHANGELOG: &str = include_str!("../tests/fixtures/rpm/changelog.txt");
+
+    #[test]
+    fn parse() {
+        let entries = parse_changelog(CHANGELOG);
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[0].header, "Mon May 06 2024 Jane Doe <jane@example.com> - 1.2-1");
+        assert_eq!(entries[0].version(), Some("1.2-1"));
+        assert_eq!(
+            entries[0].text,
+            "- Update to version 1.2\n- Fix crash on startup (rhbz#2000001)"
+        );
+
+        assert_eq!(entries[2].version(), Some("1.1-1"));
+        // entries without version in the header
+        assert_eq!(entries[3].version(), None);
+    }
+
+    #[test]
+    fn excerpts() {
+        let entries = parse_changelog(CHANGELOG);
+
+        let new = excerpt(&entries, &[String::from("1.1-1.fc40")]);
+        assert_eq!(new.entries.len(), 2);
+        assert_eq!(new.since.as_deref(), Some("1.1-1"));
+        assert!(!new.truncated);
+
+        // only the most recent entry is shown if the baseline version is unknown
+        let new = excerpt(&entries, &[]);
+        assert_eq!(new.entries, entries[..1]);
+        assert_eq!(new.since, None);
+    }
+
+    #[test]
+    fn dist() {
+        assert_eq!(strip_dist("1.fc40"), "1");
+        assert_eq!(strip_dist("0.1.rc2.fc41"), "0.1.rc2");
+        assert_eq!(strip_dist("1.el9"), "1.el9");
+        assert_eq!(strip_dist("1.fcfoo"), "1.fcfoo");
+    }
+}
@@ -1,21 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use bodhi::{BodhiClient, FedoraRelease, Update};
+use bodhi::Update;
 
-use crate::config::FedoraConfig;
+use crate::config::{CommentTemplate, FedoraConfig, SoakTimeConfig};
+use crate::error::Error;
+use crate::filter::filter_installed_updates;
 use crate::nvr::NVR;
-use crate::query::{query_obsoleted, query_unpushed};
-use crate::Command;
 
-pub fn do_check_pending(args: &Command, config: Option<&FedoraConfig>) -> bool {
-    args.check_pending || {
+pub fn do_check_pending(check_pending: bool, config: Option<&FedoraConfig>) -> bool {
+    check_pending || {
         if let Some(config) = config {
             if let Some(cfg) = &config.fuf {
-                if let Some(b) = cfg.check_pending {
-                    b
-                } else {
-                    false
-                }
+                cfg.check_pending.unwrap_or_default()
             } else {
                 false
             }
@@ -25,15 +21,11 @@ pub fn do_check_pending(args: &Command, config: Option<&FedoraConfig>) -> bool {
     }
 }
 
-pub fn do_check_obsoletes(args: &Command, config: Option<&FedoraConfig>) -> bool {
-    args.check_obsoleted || {
+pub fn do_check_obsoletes(check_obsoleted: bool, config: Option<&FedoraConfig>) -> bool {
+    check_obsoleted || {
         if let Some(config) = config {
             if let Some(cfg) = &config.fuf {
-                if let Some(b) = cfg.check_obsoleted {
-                    b
-                } else {
-                    false
-                }
+                cfg.check_obsoleted.unwrap_or_default()
             } else {
                 false
             }
@@ -43,15 +35,11 @@ pub fn do_check_obsoletes(args: &Command, config: Option<&FedoraConfig>) -> bool
     }
 }
 
-pub fn do_check_unpushed(args: &Command, config: Option<&FedoraConfig>) -> bool {
-    args.check_unpushed || {
+pub fn do_skip_partial(skip_partial: bool, config: Option<&FedoraConfig>) -> bool {
+    skip_partial || {
         if let Some(config) = config {
             if let Some(cfg) = &config.fuf {
-                if let Some(b) = cfg.check_unpushed {
-                    b
-                } else {
-                    false
-                }
+                cfg.skip_partial.unwrap_or_default()
             } else {
                 false
             }
@@ -61,33 +49,76 @@ pub fn do_check_unpushed(args: &Command, config: Option<&FedoraConfig>) -> bool
     }
 }
 
-fn filter_installed_updates<'a>(
-    check_updates: &'a [Update],
-    installed_packages: &[NVR],
-    builds_for_update: &mut HashMap<String, Vec<String>>,
-) -> Result<Vec<&'a Update>, String> {
-    let mut installed_matched: Vec<&Update> = Vec::new();
-
-    for update in check_updates {
-        let nvrs = update
-            .builds
-            .iter()
-            .map(|b| b.nvr.parse())
-            .collect::<Result<Vec<NVR>, String>>()?;
-
-        for nvr in nvrs {
-            if installed_packages.contains(&nvr) {
-                installed_matched.push(update);
-
-                builds_for_update
-                    .entry(update.alias.clone())
-                    .and_modify(|e| e.push(nvr.to_string()))
-                    .or_insert_with(|| vec![nvr.to_string()]);
-            };
+pub fn do_check_running(skip_running_check: bool, config: Option<&FedoraConfig>) -> bool {
+    !skip_running_check && {
+        if let Some(config) = config {
+            if let Some(cfg) = &config.fuf {
+                cfg.check_running.unwrap_or(true)
+            } else {
+                true
+            }
+        } else {
+            true
+        }
+    }
+}
+
+pub fn installed_min_days(installed_min_days: Option<u32>, config: Option<&FedoraConfig>) -> Option<u32> {
+    installed_min_days.or_else(|| config?.fuf.as_ref()?.installed_min_days)
+}
+
+pub fn installed_max_days(installed_max_days: Option<u32>, config: Option<&FedoraConfig>) -> Option<u32> {
+    installed_max_days.or_else(|| config?.fuf.as_ref()?.installed_max_days)
+}
+
+pub fn soak_time_config(config: Option<&FedoraConfig>) -> Option<&SoakTimeConfig> {
+    config?.fuf.as_ref()?.soak_time.as_ref()
+}
+
+pub fn comment_templates(config: Option<&FedoraConfig>) -> Option<&BTreeMap<String, CommentTemplate>> {
+    config?.fuf.as_ref().map(|fuf| &fuf.templates)
+}
+
+pub fn do_include_system_info(system_info: bool, config: Option<&FedoraConfig>) -> bool {
+    system_info || {
+        if let Some(config) = config {
+            if let Some(cfg) = &config.fuf {
+                cfg.system_info.unwrap_or_default()
+            } else {
+                false
+            }
+        } else {
+            false
         }
     }
+}
 
-    Ok(installed_matched)
+pub fn do_use_inline_editor(inline_editor: bool, config: Option<&FedoraConfig>) -> bool {
+    inline_editor || {
+        if let Some(config) = config {
+            if let Some(cfg) = &config.fuf {
+                cfg.inline_editor.unwrap_or_default()
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+}
+
+pub fn do_check_unpushed(check_unpushed: bool, config: Option<&FedoraConfig>) -> bool {
+    check_unpushed || {
+        if let Some(config) = config {
+            if let Some(cfg) = &config.fuf {
+                cfg.check_unpushed.unwrap_or_default()
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
 }
 
 fn print_update_builds(
@@ -112,18 +143,13 @@ fn print_update_builds(
     }
 }
 
-pub async fn obsoleted_check(
-    bodhi: &BodhiClient,
-    release: FedoraRelease,
+pub fn obsoleted_check(
+    obsoleted_updates: &[Update],
     installed_packages: &[NVR],
     src_bin_map: &HashMap<String, Vec<String>>,
     builds_for_update: &mut HashMap<String, Vec<String>>,
-) -> Result<(), String> {
-    // get updates in "unpushed" state
-    let obsoleted_updates = query_obsoleted(bodhi, release).await?;
-    println!();
-
-    let installed_obsoleted = filter_installed_updates(&obsoleted_updates, installed_packages, builds_for_update)?;
+) -> Result<(), Error> {
+    let installed_obsoleted = filter_installed_updates(obsoleted_updates, installed_packages, builds_for_update)?;
 
     if !installed_obsoleted.is_empty() {
         println!("There are obsoleted updates installed on this system.");
@@ -135,18 +161,13 @@ pub async fn obsoleted_check(
     Ok(())
 }
 
-pub async fn unpushed_check(
-    bodhi: &BodhiClient,
-    release: FedoraRelease,
+pub fn unpushed_check(
+    unpushed_updates: &[Update],
     installed_packages: &[NVR],
     src_bin_map: &HashMap<String, Vec<String>>,
     builds_for_update: &mut HashMap<String, Vec<String>>,
-) -> Result<(), String> {
-    // get updates in "unpushed" state
-    let unpushed_updates = query_unpushed(bodhi, release).await?;
-    println!();
-
-    let installed_unpushed = filter_installed_updates(&unpushed_updates, installed_packages, builds_for_update)?;
+) -> Result<(), Error> {
+    let installed_unpushed = filter_installed_updates(unpushed_updates, installed_packages, builds_for_update)?;
 
     if !installed_unpushed.is_empty() {
         println!("There are unpushed updates installed on this system.");
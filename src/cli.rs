@@ -1,5 +1,16 @@
-use clap::Parser;
+use std::path::PathBuf;
 
+use std::ffi::OsString;
+
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+
+/// The "review" subcommand (which is also run if no subcommand is specified) queries bodhi for
+/// updates that are installed on this system and asks for feedback for each of them. Other
+/// subcommands list installed updates without prompting, manage ignored packages, check for
+/// installed obsoleted or unpushed updates, and print information about local state.
+///
 /// There are some features that are configurable with the config file located at
 /// ~/.config/fedora.toml.
 ///
@@ -13,20 +24,97 @@ use clap::Parser;
 /// equivalent to using the --check-pending CLI switch.
 ///
 /// check-obsoleted = bool: Run additional checks whether obsoleted updates are installed on the
-/// system; equivalent to using to the --check-obsoleted CLI switch.
+/// system after reviewing updates; equivalent to using to the --check-obsoleted CLI switch.
 ///
 /// check-unpushed = bool: Run additional checks whether unpushed updates are installed on the
-/// system; equivalent to using the --check-unpushed CLI switch.
+/// system after reviewing updates; equivalent to using the --check-unpushed CLI switch.
+///
+/// skip-partial = bool: Skip updates where only some of the builds are installed; equivalent to
+/// using the --skip-partial CLI switch.
+///
+/// system-info = bool: Append a description of the current system (Fedora release, architecture,
+/// kernel, desktop session, hardware model, and installed packages from the update) to comments;
+/// equivalent to using the --system-info CLI switch.
+///
+/// inline-editor = bool: Write comments directly in the terminal (with line editing, a persistent
+/// prompt history, and tab completion of actions) instead of an external editor; equivalent to
+/// using the --inline-editor CLI switch.
+///
+/// installed-min-days = number / installed-max-days = number: Skip updates that were installed
+/// less / more than the given number of days ago; equivalent to using the --installed-min-days and
+/// --installed-max-days CLI options.
+///
+/// The [fedora-update-feedback.soak-time] section can contain a minimum number of days between
+/// installing an update and providing positive karma for it: a default value ("days = number"),
+/// values for update types ("types = { security = 0 }") or packages that take precedence
+/// ("packages = { kernel = 3 }"), and whether positive karma is refused instead of asking for
+/// confirmation ("strict = bool").
+///
+/// The [fedora-update-feedback.templates.NAME] sections can contain named comment templates, with
+/// a "text" that can contain the {alias}, {builds}, {release}, {installed_for}, and {hostname}
+/// placeholders, and optionally the "karma" (1, 0, or -1) that is provided with the comment.
+///
+/// bodhi-url = string: Target a different instance of bodhi: either "production" (default),
+/// "staging", or the base URL of a custom bodhi server; equivalent to using the --bodhi-url CLI
+/// option.
+///
+/// openid-url = string: OpenID endpoint that is used for authenticating with a custom bodhi
+/// server; equivalent to using the --openid-url CLI option.
 ///
 /// save-password: Try to saves the FAS password in the session keyring. To ignore
 /// a password that was stored in the session keyring (for example, if you changed
 /// it, or made a typo when it was prompted), use the --ignore-keyring CLI switch
 /// to ask for the password again.
+///
+/// Feedback that could not be submitted (for example, because bodhi was not reachable) is saved in
+/// a local queue instead of being discarded. With the --queue CLI switch, all feedback is saved in
+/// this queue without being submitted, and no password is required. Queued feedback can be
+/// submitted later with the "submit" subcommand.
+///
+/// With the --batch CLI switch, feedback for multiple updates is read from a TOML or JSON file and
+/// submitted without any interactive prompts. Every [[feedback]] entry in this file needs to
+/// specify either an "update" alias or a source "package" name, and can contain values for
+/// "comment", "karma" (1, 0, or -1), "bugs" (map from bug IDs to karma), and "test-cases" (map
+/// from test case names to karma).
 #[derive(Debug, Parser)]
 pub struct Command {
+    /// subcommand (if any)
+    #[command(subcommand)]
+    pub action: Option<Action>,
+    /// arguments that are accepted by all subcommands
+    #[command(flatten)]
+    pub common: CommonArgs,
+    /// arguments for the "review" subcommand, which is run if no subcommand is specified
+    #[command(flatten)]
+    pub review: ReviewArgs,
+    /// flags from before subcommands were introduced
+    #[command(flatten)]
+    pub legacy: LegacyArgs,
+}
+
+/// Arguments that are accepted by all subcommands.
+#[derive(Clone, Debug, Default, Args)]
+pub struct CommonArgs {
     /// Override or provide FAS username
-    #[arg(long, short)]
+    #[arg(long, short, global = true)]
     pub username: Option<String>,
+    /// Ignore password stored in session keyring
+    #[arg(long, global = true)]
+    pub ignore_keyring: bool,
+    /// Bodhi instance to use ("production", "staging", or a custom URL)
+    #[arg(long, value_name = "URL", global = true)]
+    pub bodhi_url: Option<String>,
+    /// OpenID endpoint for authenticating with a custom bodhi instance
+    #[arg(long, value_name = "URL", global = true)]
+    pub openid_url: Option<String>,
+    /// Print more progress information and command output
+    #[arg(long, short = 'v', global = true)]
+    pub verbose: bool,
+}
+
+/// Arguments for reviewing installed updates.
+#[derive(Clone, Debug, Default, Args)]
+pub struct ReviewArgs {
     /// Check for installed obsolete updates
     #[arg(long, short = 'O')]
     pub check_obsoleted: bool,
@@ -42,22 +130,296 @@ pub struct Command {
     /// Check for installed unpushed updates
     #[arg(long, short = 'U')]
     pub check_unpushed: bool,
+    /// Skip updates where only some of the builds are installed
+    #[arg(long)]
+    pub skip_partial: bool,
+    /// Do not check whether installed updates are in use by the running system yet
+    #[arg(long)]
+    pub skip_running_check: bool,
+    /// Append a description of the current system to comments
+    #[arg(long)]
+    pub system_info: bool,
+    /// Write comments in the terminal instead of an external editor
+    #[arg(long)]
+    pub inline_editor: bool,
+    /// Skip updates that were installed less than DAYS days ago
+    #[arg(long, value_name = "DAYS")]
+    pub installed_min_days: Option<u32>,
+    /// Skip updates that were installed more than DAYS days ago
+    #[arg(long, value_name = "DAYS")]
+    pub installed_max_days: Option<u32>,
     /// Clear ignored updates
     #[arg(long, short = 'i')]
     pub clear_ignored: bool,
-    /// Ignore password stored in session keyring
-    #[arg(long)]
-    pub ignore_keyring: bool,
-    /// Add a package name to the list of ignored packages
-    #[arg(long, short = 'a')]
+    /// Save feedback in the local queue instead of submitting it
+    #[arg(long, short = 'q')]
+    pub queue: bool,
+    /// Read feedback from a file and submit it without prompting
+    #[arg(long, value_name = "FILE")]
+    pub batch: Option<PathBuf>,
+}
+
+/// Arguments for listing installed updates.
+#[derive(Clone, Debug, Default, Args)]
+pub struct ListArgs {
+    /// Include updates in "pending" state
+    #[arg(long, short = 'P')]
+    pub check_pending: bool,
+    /// Include updates that were already commented on
+    #[arg(long, short = 'c')]
+    pub check_commented: bool,
+    /// Include updates that were previously ignored
+    #[arg(long, short = 'I')]
+    pub check_ignored: bool,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    pub format: ListFormat,
+}
+
+/// Output formats for listing installed updates.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ListFormat {
+    /// human-readable table with aligned columns
+    #[default]
+    Table,
+    /// JSON array with one object per update
+    Json,
+}
+
+/// Flags from before subcommands were introduced, which are still accepted without a subcommand.
+#[derive(Clone, Debug, Default, Args)]
+pub struct LegacyArgs {
+    /// Add a package name to the list of ignored packages (same as "ignore add")
+    #[arg(long, short = 'a', value_name = "PACKAGE", hide = true)]
     pub add_ignored_package: Option<String>,
-    /// Remove a package name from the list of ignored packages
-    #[arg(long, short = 'r')]
+    /// Remove a package name from the list of ignored packages (same as "ignore remove")
+    #[arg(long, short = 'r', value_name = "PACKAGE", hide = true)]
     pub remove_ignored_package: Option<String>,
-    /// Print the list of ignored packages and updates
-    #[arg(long, short = 'p')]
+    /// Print the list of ignored packages and updates (same as "ignore list")
+    #[arg(long, short = 'p', hide = true)]
     pub print_ignored: bool,
-    /// Print more progress information and command output
-    #[arg(long, short = 'v')]
-    pub verbose: bool,
+    /// Submit feedback that was saved in the local queue (same as "submit")
+    #[arg(long, short = 's', hide = true, conflicts_with_all = ["queue", "batch"])]
+    pub submit_queued: bool,
+}
+
+/// Subcommands of `fedora-update-feedback`.
+#[derive(Clone, Debug, Subcommand)]
+pub enum Action {
+    /// Review installed updates and provide feedback (default)
+    Review(ReviewArgs),
+    /// List installed updates that are waiting for feedback without prompting
+    List(ListArgs),
+    /// Manage the lists of ignored packages and updates
+    Ignore {
+        /// action for the lists of ignored packages and updates
+        #[command(subcommand)]
+        action: IgnoreAction,
+    },
+    /// Check for installed updates that will never be pushed to stable
+    Check {
+        /// kind of check to run
+        #[command(subcommand)]
+        check: Check,
+    },
+    /// Submit feedback that was saved in the local queue
+    Submit,
+    /// Print information about the configuration and local state
+    Status,
+}
+
+/// Subcommands for managing the lists of ignored packages and updates.
+#[derive(Clone, Debug, Subcommand)]
+pub enum IgnoreAction {
+    /// Add a package name to the list of ignored packages
+    Add {
+        /// source package name
+        package: String,
+    },
+    /// Remove a package name from the list of ignored packages
+    Remove {
+        /// source package name
+        package: String,
+    },
+    /// Print the lists of ignored packages and updates
+    List,
+    /// Clear the lists of ignored packages and updates
+    Clear,
+}
+
+/// Additional checks for installed updates.
+#[derive(Clone, Copy, Debug, Subcommand)]
+pub enum Check {
+    /// Check for installed obsoleted updates
+    Obsoleted,
+    /// Check for installed unpushed updates
+    Unpushed,
+}
+
+impl Command {
+    /// This method parses command line arguments, and exits with an error message if flags for
+    /// the default "review" subcommand are combined with a different subcommand.
+    pub fn parse_checked() -> Command {
+        Command::try_parse_checked_from(std::env::args_os()).unwrap_or_else(|error| error.exit())
+    }
+
+    /// This method parses command line arguments from an iterator, and returns an error if flags
+    /// for the default "review" subcommand are combined with a different subcommand.
+    pub fn try_parse_checked_from<I, T>(args: I) -> Result<Command, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut command = Command::command();
+        let matches = command.try_get_matches_from_mut(args)?;
+        check_conflicts(&mut command, &matches)?;
+        Command::from_arg_matches(&matches)
+    }
+
+    /// This method returns the actions that were requested on the command line. If no subcommand
+    /// was specified, the flags from before subcommands were introduced are translated into the
+    /// equivalent subcommands, and "review" is the default.
+    pub fn actions(&self) -> Vec<Action> {
+        if let Some(action) = &self.action {
+            return vec![action.clone()];
+        }
+
+        let mut actions = Vec::new();
+
+        let legacy = &self.legacy;
+        let manage_ignored =
+            legacy.add_ignored_package.is_some() || legacy.remove_ignored_package.is_some() || legacy.print_ignored;
+
+        // the lists of ignored packages and updates were cleared before they were modified or printed
+        if manage_ignored && self.review.clear_ignored {
+            actions.push(Action::Ignore {
+                action: IgnoreAction::Clear,
+            });
+        }
+
+        if let Some(package) = &legacy.add_ignored_package {
+            actions.push(Action::Ignore {
+                action: IgnoreAction::Add {
+                    package: package.clone(),
+                },
+            });
+        }
+
+        if let Some(package) = &legacy.remove_ignored_package {
+            actions.push(Action::Ignore {
+                action: IgnoreAction::Remove {
+                    package: package.clone(),
+                },
+            });
+        }
+
+        if legacy.print_ignored {
+            actions.push(Action::Ignore {
+                action: IgnoreAction::List,
+            });
+        }
+
+        if !actions.is_empty() {
+            return actions;
+        }
+
+        if legacy.submit_queued {
+            return vec![Action::Submit];
+        }
+
+        vec![Action::Review(self.review.clone())]
+    }
+}
+
+// arguments that are accepted by all subcommands are marked as "global", which is incompatible with
+// clap's "args_conflicts_with_subcommands" setting, so conflicts are checked manually instead
+fn check_conflicts(command: &mut clap::Command, matches: &ArgMatches) -> Result<(), clap::Error> {
+    let Some((name, _)) = matches.subcommand() else {
+        return Ok(());
+    };
+
+    let toplevel = LegacyArgs::augment_args(ReviewArgs::augment_args(clap::Command::new("toplevel")));
+
+    for arg in toplevel.get_arguments() {
+        if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+            return Err(command.error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "the argument '--{}' cannot be used with the '{}' subcommand",
+                    arg.get_long().unwrap_or_default(),
+                    name
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn actions(args: &[&str]) -> Vec<Action> {
+        Command::try_parse_checked_from(std::iter::once("fedora-update-feedback").chain(args.iter().copied()))
+            .unwrap()
+            .actions()
+    }
+
+    #[test]
+    fn legacy() {
+        match actions(&["--check-pending", "--queue"]).as_slice() {
+            [Action::Review(args)] => assert!(args.check_pending && args.queue),
+            actions => panic!("Unexpected actions: {:?}", actions),
+        }
+
+        match actions(&["--add-ignored-package", "foo", "--print-ignored"]).as_slice() {
+            [Action::Ignore {
+                action: IgnoreAction::Add { package },
+            }, Action::Ignore {
+                action: IgnoreAction::List,
+            }] => assert_eq!(package, "foo"),
+            actions => panic!("Unexpected actions: {:?}", actions),
+        }
+
+        // the lists of ignored packages and updates are cleared before a package is added
+        match actions(&["-i", "-a", "foo"]).as_slice() {
+            [Action::Ignore {
+                action: IgnoreAction::Clear,
+            }, Action::Ignore {
+                action: IgnoreAction::Add { package },
+            }] => assert_eq!(package, "foo"),
+            actions => panic!("Unexpected actions: {:?}", actions),
+        }
+
+        assert!(matches!(actions(&["--submit-queued"]).as_slice(), [Action::Submit]));
+    }
+
+    #[test]
+    fn subcommands() {
+        match actions(&["ignore", "remove", "foo", "--verbose"]).as_slice() {
+            [Action::Ignore {
+                action: IgnoreAction::Remove { package },
+            }] => assert_eq!(package, "foo"),
+            actions => panic!("Unexpected actions: {:?}", actions),
+        }
+
+        assert!(matches!(
+            actions(&["check", "unpushed"]).as_slice(),
+            [Action::Check { check: Check::Unpushed }]
+        ));
+
+        match actions(&["list", "--format", "json"]).as_slice() {
+            [Action::List(args)] => assert_eq!(args.format, ListFormat::Json),
+            actions => panic!("Unexpected actions: {:?}", actions),
+        }
+
+        // flags from the default subcommand cannot be combined with other subcommands
+        assert!(Command::try_parse_checked_from(["fedora-update-feedback", "--queue", "status"]).is_err());
+        assert!(Command::try_parse_checked_from(["fedora-update-feedback", "--submit-queued", "--queue"]).is_err());
+
+        // arguments that are accepted by all subcommands can be specified before the subcommand
+        assert!(matches!(actions(&["--verbose", "status"]).as_slice(), [Action::Status]));
+    }
 }
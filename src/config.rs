@@ -1,6 +1,11 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bodhi::Karma;
 use serde::Deserialize;
 use tokio::fs::read_to_string;
 
+use crate::error::Error;
+
 /// This struct represents the contents of a `~/.config/fedora.toml` file.
 /// It includes a mandatory `[FAS]` section, and optional sections for tools.
 /// It should look something like this:
@@ -13,6 +18,18 @@ use tokio::fs::read_to_string;
 /// check-obsoleted = false
 /// check-pending = true
 /// check-unpushed = true
+/// check-running = false
+/// bodhi-url = "staging"
+/// installed-min-days = 1
+///
+/// [fedora-update-feedback.soak-time]
+/// days = 1
+/// types = { newpackage = 3 }
+/// packages = { kernel = 2 }
+///
+/// [fedora-update-feedback.templates.works-for-me]
+/// text = "Works fine on {release}, installed for {installed_for}."
+/// karma = 1
 /// ```
 #[derive(Debug, Deserialize)]
 pub struct FedoraConfig {
@@ -43,17 +60,74 @@ pub struct FUFConfig {
     /// Check for installed unpushed updates
     #[serde(rename = "check-unpushed")]
     pub check_unpushed: Option<bool>,
+    /// Skip updates where only some builds are installed
+    #[serde(rename = "skip-partial")]
+    pub skip_partial: Option<bool>,
+    /// Check whether installed updates are in use by the running system yet (enabled by default)
+    #[serde(rename = "check-running")]
+    pub check_running: Option<bool>,
+    /// Append a description of the current system to comments
+    #[serde(rename = "system-info")]
+    pub system_info: Option<bool>,
+    /// Write comments with the inline editor instead of an external editor
+    #[serde(rename = "inline-editor")]
+    pub inline_editor: Option<bool>,
+    /// Skip updates that were installed less than this number of days ago
+    #[serde(rename = "installed-min-days")]
+    pub installed_min_days: Option<u32>,
+    /// Skip updates that were installed more than this number of days ago
+    #[serde(rename = "installed-max-days")]
+    pub installed_max_days: Option<u32>,
+    /// Minimum time between installing updates and providing positive karma
+    #[serde(rename = "soak-time")]
+    pub soak_time: Option<SoakTimeConfig>,
+    /// Named templates for comments
+    #[serde(default)]
+    pub templates: BTreeMap<String, CommentTemplate>,
     /// Save password in session keyring
     #[serde(rename = "save-password")]
     pub save_password: Option<bool>,
+    /// Bodhi instance ("production", "staging", or a custom URL)
+    #[serde(rename = "bodhi-url")]
+    pub bodhi_url: Option<String>,
+    /// OpenID endpoint for a custom bodhi instance
+    #[serde(rename = "openid-url")]
+    pub openid_url: Option<String>,
+}
+
+/// This config file section contains the minimum number of days between installing an update and
+/// providing positive karma for it. Values for specific packages take precedence over values for
+/// update types, which take precedence over the default value.
+#[derive(Debug, Default, Deserialize)]
+pub struct SoakTimeConfig {
+    /// Default minimum number of days
+    pub days: Option<u32>,
+    /// Minimum number of days for update types (for example, "security" or "newpackage")
+    #[serde(default)]
+    pub types: HashMap<String, u32>,
+    /// Minimum number of days for updates that contain specific source packages
+    #[serde(default)]
+    pub packages: HashMap<String, u32>,
+    /// Refuse positive karma instead of asking for confirmation
+    pub strict: Option<bool>,
 }
 
-/// This helper function reads and parses the configuration file.
-pub async fn get_config() -> Result<FedoraConfig, String> {
+/// This config file section contains a named template for comments.
+#[derive(Debug, Deserialize)]
+pub struct CommentTemplate {
+    /// Text of the comment, which can contain placeholders like `{alias}` or `{release}`
+    pub text: String,
+    /// Karma that is provided together with the comment (asked for if not specified)
+    pub karma: Option<Karma>,
+}
+
+/// This helper function reads and parses the configuration file. Returns `None` if the file does
+/// not exist.
+pub async fn get_config() -> Result<Option<FedoraConfig>, Error> {
     let home = match dirs::home_dir() {
         Some(path) => path,
         None => {
-            return Err(String::from("Unable to determine $HOME."));
+            return Err(Error::config("Unable to determine $HOME."));
         },
     };
 
@@ -61,31 +135,34 @@ pub async fn get_config() -> Result<FedoraConfig, String> {
 
     let config_str = match read_to_string(&config_path).await {
         Ok(string) => string,
-        Err(_) => {
-            return Err(String::from(
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(Error::config_with(
                 "Unable to read configuration file from ~/.config/fedora.toml",
+                error,
             ));
         },
     };
 
     let config: FedoraConfig = match toml::from_str(&config_str) {
         Ok(config) => config,
-        Err(_) => {
-            return Err(String::from(
+        Err(error) => {
+            return Err(Error::config_with(
                 "Unable to parse configuration file from ~/.config/fedora.toml",
+                error,
             ));
         },
     };
 
-    Ok(config)
+    Ok(Some(config))
 }
 
 /// This helper function reads the username from the legacy `~/.fedora.upn` file.
-pub async fn get_legacy_username() -> Result<Option<String>, String> {
+pub async fn get_legacy_username() -> Result<Option<String>, Error> {
     let home = match dirs::home_dir() {
         Some(path) => path,
         None => {
-            return Err(String::from("Unable to determine $HOME."));
+            return Err(Error::config("Unable to determine $HOME."));
         },
     };
 
@@ -97,7 +174,7 @@ pub async fn get_legacy_username() -> Result<Option<String>, String> {
             return if error.kind() == std::io::ErrorKind::NotFound {
                 Ok(None)
             } else {
-                Err(error.to_string())
+                Err(Error::config_with("Unable to read username from ~/.fedora.upn", error))
             };
         },
     };
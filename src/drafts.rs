@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use tokio::fs::{create_dir_all, read_to_string, remove_file};
+
+use crate::error::Error;
+
+const DIR_NAME: &str = "fedora-update-feedback.drafts";
+
+/// This helper function returns the path of the draft comment for an update. Drafts are kept until
+/// feedback for the update was submitted successfully, so comments are not lost if the editor
+/// crashes or submitting feedback fails.
+pub fn get_draft_path(alias: &str) -> Result<PathBuf, Error> {
+    let data_dir = dirs::data_dir().ok_or_else(|| Error::config("Unable to determine the data directory."))?;
+    Ok(data_dir.join(DIR_NAME).join(format!("{}.md", alias)))
+}
+
+/// Helper function to get the draft comment for an update from a previous session, if there is one.
+pub async fn get_draft(alias: &str) -> Option<String> {
+    read_to_string(get_draft_path(alias).ok()?)
+        .await
+        .ok()
+        .filter(|draft| !draft.trim().is_empty())
+}
+
+/// Helper function to create the directory for draft comments, and return the path of the draft
+/// comment for an update.
+pub async fn prepare_draft(alias: &str) -> Result<PathBuf, Error> {
+    let path = get_draft_path(alias)?;
+
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)
+            .await
+            .map_err(|error| Error::io(format!("Failed to create {}", dir.display()), error))?;
+    }
+
+    Ok(path)
+}
+
+/// Helper function to remove the draft comment for an update (if there is one).
+pub async fn remove_draft(alias: &str) {
+    // there is nothing to remove if feedback was not written in an external editor
+    if let Ok(path) = get_draft_path(alias) {
+        let _ = remove_file(path).await;
+    }
+}
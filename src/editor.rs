@@ -0,0 +1,202 @@
+use bodhi::{Karma, Update};
+
+// line that separates the comment from context and feedback fields (like `git commit --verbose`)
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// This struct contains feedback that was entered in the editor buffer for an update.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct EditedFeedback {
+    /// comment text (everything above the scissors line)
+    pub comment: Option<String>,
+    /// karma for the update
+    pub karma: Option<Karma>,
+    /// bug feedback
+    pub bug_feedback: Vec<(u32, Karma)>,
+    /// test case feedback
+    pub testcase_feedback: Vec<(String, Karma)>,
+    /// lines with feedback fields that could not be parsed
+    pub invalid: Vec<String>,
+    /// whether the feedback fields were present (they are missing if the editor replaced the
+    /// whole buffer, in which case the whole text is used as the comment)
+    pub has_fields: bool,
+}
+
+/// This helper function formats a karma value for a feedback field.
+fn karma_value(karma: Option<Karma>) -> &'static str {
+    match karma {
+        Some(Karma::Positive) => " +1",
+        Some(Karma::Neutral) => " 0",
+        Some(Karma::Negative) => " -1",
+        None => "",
+    }
+}
+
+/// This function prepares the editor buffer for an update: the (optional) initial comment, and
+/// below a scissors line, some commented-out context about the update, and fields for karma and
+/// bug and test case feedback.
+pub fn format_buffer(update: &Update, binaries: &[&str], comment: &str, karma: Option<Karma>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    lines.push(comment.trim().to_string());
+    lines.push(String::new());
+    lines.push(SCISSORS.to_string());
+    lines.push(String::from(
+        "# Everything above this line is submitted as the comment. Do not modify or",
+    ));
+    lines.push(String::from(
+        "# remove the line above. Below, provide karma for the update and feedback for",
+    ));
+    lines.push(String::from(
+        "# bugs and test cases (+1, 0, or -1), or leave values empty to skip them.",
+    ));
+    lines.push(String::from("#"));
+    lines.push(format!("# {}: {}", update.alias, update.title));
+
+    if !binaries.is_empty() {
+        lines.push(String::from("# Installed packages:"));
+        for binary in binaries {
+            lines.push(format!("#   {}", binary));
+        }
+    }
+
+    for bug in &update.bugs {
+        let title = bug.title.as_deref().unwrap_or("(None)");
+        lines.push(format!("# Bug {}: {}", bug.bug_id, title.trim()));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("karma:{}", karma_value(karma)));
+
+    for bug in &update.bugs {
+        lines.push(format!("bug {}:", bug.bug_id));
+    }
+
+    if let Some(test_cases) = &update.test_cases {
+        for test_case in test_cases {
+            lines.push(format!("test-case {}:", test_case.name));
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// This helper function parses the value of a feedback field. Empty values mean that feedback is
+/// skipped.
+fn parse_value(value: &str) -> Result<Option<Karma>, ()> {
+    match value.trim() {
+        "" => Ok(None),
+        value => value.parse().map(Some).map_err(|_| ()),
+    }
+}
+
+/// This function parses the contents of an editor buffer that was prepared with
+/// [`format_buffer`].
+pub fn parse_buffer(buffer: &str) -> EditedFeedback {
+    let (comment, fields) = match buffer.split_once(SCISSORS) {
+        Some((comment, fields)) => (comment, Some(fields)),
+        None => (buffer, None),
+    };
+
+    let mut feedback = EditedFeedback {
+        comment: Some(comment.trim())
+            .filter(|comment| !comment.is_empty())
+            .map(|comment| format!("{}\n", comment)),
+        has_fields: fields.is_some(),
+        ..Default::default()
+    };
+
+    for line in fields.unwrap_or_default().lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // test case names can contain colons, but values cannot
+        let Some((field, value)) = line.rsplit_once(':') else {
+            feedback.invalid.push(line.to_string());
+            continue;
+        };
+
+        let Ok(value) = parse_value(value) else {
+            feedback.invalid.push(line.to_string());
+            continue;
+        };
+
+        if field == "karma" {
+            feedback.karma = value;
+        } else if let Some(id) = field.strip_prefix("bug ") {
+            match (id.trim().parse::<u32>(), value) {
+                (Ok(id), Some(value)) => feedback.bug_feedback.push((id, value)),
+                (Ok(_), None) => {},
+                (Err(_), _) => feedback.invalid.push(line.to_string()),
+            }
+        } else if let Some(name) = field.strip_prefix("test-case ") {
+            if let Some(value) = value {
+                feedback.testcase_feedback.push((name.trim().to_string(), value));
+            }
+        } else {
+            feedback.invalid.push(line.to_string());
+        }
+    }
+
+    feedback
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const UPDATES: &str = include_str!("../tests/fixtures/updates.json");
+
+    #[test]
+    fn roundtrip() {
+        let updates: Vec<Update> = serde_json::from_str(UPDATES).unwrap();
+        let update = &updates[0];
+
+        let buffer = format_buffer(update, &["foo-1.2-1.fc40.x86_64"], "", Some(Karma::Positive));
+        assert!(buffer.starts_with(&format!("\n\n{}\n", SCISSORS)));
+        assert!(buffer.contains("#   foo-1.2-1.fc40.x86_64\n"));
+        assert!(buffer.contains("\nkarma: +1\nbug 2000001:\n"));
+
+        // unchanged buffer: no comment, only karma
+        let feedback = parse_buffer(&buffer);
+        assert_eq!(feedback.comment, None);
+        assert_eq!(feedback.karma, Some(Karma::Positive));
+        assert!(feedback.bug_feedback.is_empty());
+        assert!(feedback.has_fields);
+
+        let edited = buffer
+            .replacen("\n\n", "Works fine.\n\n# not a heading\n\n", 1)
+            .replace("bug 2000001:", "bug 2000001: 1");
+        let feedback = parse_buffer(&edited);
+        assert_eq!(feedback.comment.as_deref(), Some("Works fine.\n\n# not a heading\n"));
+        assert_eq!(feedback.bug_feedback, vec![(2000001, Karma::Positive)]);
+        assert!(feedback.invalid.is_empty());
+    }
+
+    #[test]
+    fn fields() {
+        let buffer = format!(
+            "Comment\n{}\n# context\nkarma: -1\nbug 123: 0\nbug 456:\ntest-case QA:Testcase_foo: +1\nbug abc: 1\nkarma: maybe\n",
+            SCISSORS
+        );
+
+        let feedback = parse_buffer(&buffer);
+        assert_eq!(feedback.comment.as_deref(), Some("Comment\n"));
+        assert_eq!(feedback.karma, Some(Karma::Negative));
+        assert_eq!(feedback.bug_feedback, vec![(123, Karma::Neutral)]);
+        assert_eq!(
+            feedback.testcase_feedback,
+            vec![(String::from("QA:Testcase_foo"), Karma::Positive)]
+        );
+        assert_eq!(feedback.invalid, vec!["bug abc: 1", "karma: maybe"]);
+    }
+
+    #[test]
+    fn replaced() {
+        let feedback = parse_buffer("Works fine.\n");
+        assert_eq!(feedback.comment.as_deref(), Some("Works fine.\n"));
+        assert!(!feedback.has_fields);
+    }
+}
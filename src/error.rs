@@ -0,0 +1,281 @@
+use std::error::Error as StdError;
+use std::fmt::Write;
+use std::process::Output;
+
+type Source = Box<dyn StdError + Send + Sync>;
+
+// exit codes follow the conventions from sysexits.h
+const EX_DATAERR: u8 = 65;
+const EX_UNAVAILABLE: u8 = 69;
+const EX_OSERR: u8 = 71;
+const EX_IOERR: u8 = 74;
+const EX_NOPERM: u8 = 77;
+const EX_CONFIG: u8 = 78;
+
+/// This enum represents all errors that can occur in `fedora-update-feedback`.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// configuration file, username, or bodhi instance settings are missing or invalid
+    #[error("{message}")]
+    Config {
+        /// description of the problem
+        message: String,
+        /// underlying error, if any
+        #[source]
+        source: Option<Source>,
+    },
+    /// session keyring could not be accessed
+    #[error("{message}")]
+    Keyring {
+        /// description of the problem
+        message: String,
+        /// underlying error from the SecretService client
+        #[source]
+        source: secret_service::Error,
+    },
+    /// a subprocess (dnf, rpm, rpm-ostree) exited with an error
+    #[error("{command} failed ({})", match .code { Some(code) => format!("exit code {}", code), None => String::from("terminated by signal") })]
+    Subprocess {
+        /// command that was run
+        command: String,
+        /// exit code of the process, if it was not terminated by a signal
+        code: Option<i32>,
+        /// standard error output of the process
+        stderr: String,
+    },
+    /// output of a subprocess or contents of a file could not be parsed
+    #[error("{message}")]
+    Parse {
+        /// description of the problem
+        message: String,
+        /// underlying error, if any
+        #[source]
+        source: Option<Source>,
+    },
+    /// a request to bodhi failed
+    #[error("{message}")]
+    Bodhi {
+        /// description of the problem
+        message: String,
+        /// underlying error from the bodhi client, if any
+        #[source]
+        source: Option<Source>,
+    },
+    /// feedback could not be submitted to bodhi, and was saved in the local queue instead
+    #[error("{message}")]
+    Submit {
+        /// description of the problem
+        message: String,
+    },
+    /// reading or writing a file or a terminal failed
+    #[error("{message}")]
+    Io {
+        /// description of the problem
+        message: String,
+        /// underlying I/O error
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    /// This method constructs a configuration error without an underlying cause.
+    pub fn config(message: impl Into<String>) -> Error {
+        Error::Config {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// This method constructs a configuration error with an underlying cause.
+    pub fn config_with(message: impl Into<String>, source: impl Into<Source>) -> Error {
+        Error::Config {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// This method constructs a keyring error.
+    pub fn keyring(message: impl Into<String>, source: secret_service::Error) -> Error {
+        Error::Keyring {
+            message: message.into(),
+            source,
+        }
+    }
+
+    /// This method constructs a parse error without an underlying cause.
+    pub fn parse(message: impl Into<String>) -> Error {
+        Error::Parse {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// This method constructs a parse error with an underlying cause.
+    pub fn parse_with(message: impl Into<String>, source: impl Into<Source>) -> Error {
+        Error::Parse {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// This method constructs a bodhi error without an underlying cause.
+    pub fn bodhi(message: impl Into<String>) -> Error {
+        Error::Bodhi {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// This method constructs a bodhi error with an underlying cause.
+    pub fn bodhi_with(message: impl Into<String>, source: impl Into<Source>) -> Error {
+        Error::Bodhi {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// This method constructs an error for feedback that could not be submitted.
+    pub fn submit(message: impl Into<String>) -> Error {
+        Error::Submit {
+            message: message.into(),
+        }
+    }
+
+    /// This method constructs an I/O error.
+    pub fn io(message: impl Into<String>, source: std::io::Error) -> Error {
+        Error::Io {
+            message: message.into(),
+            source,
+        }
+    }
+
+    /// This method checks the exit status of a finished subprocess, and returns an error that
+    /// includes its standard error output if it was not successful.
+    pub fn check_output(command: &str, output: &Output) -> Result<(), Error> {
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::Subprocess {
+                command: command.to_string(),
+                code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            })
+        }
+    }
+
+    /// This method returns the exit code of the process for this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Config { .. } => EX_CONFIG,
+            Error::Keyring { .. } => EX_NOPERM,
+            Error::Subprocess { .. } => EX_OSERR,
+            Error::Parse { .. } => EX_DATAERR,
+            Error::Bodhi { .. } | Error::Submit { .. } => EX_UNAVAILABLE,
+            Error::Io { .. } => EX_IOERR,
+        }
+    }
+
+    /// This method returns a hint for how the problem might be fixed, if there is one.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Error::Config { .. } => Some(
+                "Check the [FAS] and [fedora-update-feedback] sections in ~/.config/fedora.toml, \
+                 or pass --username, --bodhi-url, and --openid-url on the command line.",
+            ),
+            Error::Keyring { .. } => Some(
+                "Provide the password with the FUF_PASSWORD environment variable, \
+                 or set save-password = false in ~/.config/fedora.toml.",
+            ),
+            Error::Subprocess { .. } => {
+                Some("Check that the command works when it is run manually, and that the RPM database is not locked.")
+            },
+            Error::Parse { .. } => None,
+            Error::Bodhi { .. } => Some("Check the network connection and your FAS credentials."),
+            Error::Submit { .. } => Some(
+                "Check the network connection and your FAS credentials. \
+                 Feedback that could not be submitted is kept in the local queue.",
+            ),
+            Error::Io { source, .. } if source.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Some("Use the --batch CLI switch for providing feedback non-interactively.")
+            },
+            Error::Io { .. } => None,
+        }
+    }
+
+    /// This method renders the error, including the chain of underlying errors, standard error
+    /// output of failed subprocesses, and a hint (if available).
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+
+        let mut source = self.source();
+        while let Some(error) = source {
+            let _ = write!(report, "\n  Caused by: {}", error);
+            source = error.source();
+        }
+
+        if let Error::Subprocess { stderr, .. } = self {
+            for line in stderr.lines() {
+                let _ = write!(report, "\n  | {}", line);
+            }
+        }
+
+        if let Some(hint) = self.hint() {
+            let _ = write!(report, "\nHint: {}", hint);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    #[test]
+    fn subprocess() {
+        let output = Output {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: Vec::new(),
+            stderr: b"Error: rpmdb open failed\n".to_vec(),
+        };
+
+        let error = Error::check_output("dnf repoquery", &output).unwrap_err();
+        assert_eq!(error.exit_code(), EX_OSERR);
+        assert_eq!(
+            error.report(),
+            "dnf repoquery failed (exit code 1)\n  | Error: rpmdb open failed\nHint: \
+             Check that the command works when it is run manually, and that the RPM database is not locked."
+        );
+    }
+
+    #[test]
+    fn chain() {
+        let error = Error::io(
+            "Failed to read from stdin",
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "standard input was closed"),
+        );
+        assert_eq!(error.exit_code(), EX_IOERR);
+        assert_eq!(
+            error.report(),
+            "Failed to read from stdin\n  Caused by: standard input was closed\nHint: \
+             Use the --batch CLI switch for providing feedback non-interactively."
+        );
+    }
+
+    #[test]
+    fn queue_hint() {
+        // only failed submissions mention the local queue, failed queries and logins do not
+        let error = Error::bodhi("Failed to query 'testing' updates");
+        assert_eq!(error.exit_code(), EX_UNAVAILABLE);
+        assert!(!error.report().contains("local queue"));
+
+        let error = Error::submit("Failed to provide 1 feedback item.");
+        assert_eq!(error.exit_code(), EX_UNAVAILABLE);
+        assert!(error.report().contains("local queue"));
+    }
+}
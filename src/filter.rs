@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use bodhi::Update;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::error::Error;
+use crate::ignore::IgnoreLists;
+use crate::nvr::NVR;
+use crate::sysinfo::InstalledPackages;
+
+/// This helper function returns the names of all (source) packages that are contained in an update.
+pub fn packages_in_update(update: &Update) -> Vec<String> {
+    let names: Vec<String> = update
+        .builds
+        .iter()
+        .map(|build| {
+            build
+                .nvr
+                .parse::<NVR>()
+                .expect("Failed to parse a build NVR from bodhi, this should not happen.")
+                .n
+        })
+        .collect();
+    names
+}
+
+/// This struct represents a build from an update that is not installed on the current system.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissingBuild {
+    /// NVR of the build from the update
+    pub nvr: String,
+    /// NVR of a different version of the same package that is installed instead (if any)
+    pub installed: Option<String>,
+}
+
+/// This helper function returns the builds from an update that are not installed on the current
+/// system. Updates where only some builds are installed cannot be tested completely.
+pub fn missing_builds(update: &Update, installed_packages: &[NVR]) -> Result<Vec<MissingBuild>, Error> {
+    let mut missing = Vec::new();
+
+    for build in &update.builds {
+        let nvr: NVR = build.nvr.parse()?;
+
+        if installed_packages.contains(&nvr) {
+            continue;
+        }
+
+        let installed = installed_packages
+            .iter()
+            .find(|installed| installed.n == nvr.n)
+            .map(|installed| installed.to_string());
+
+        missing.push(MissingBuild {
+            nvr: build.nvr.clone(),
+            installed,
+        });
+    }
+
+    Ok(missing)
+}
+
+/// This helper function returns all updates which contain builds that are installed on the current
+/// system (without duplicates), and remembers which builds are installed for which update.
+pub fn filter_installed_updates<'a>(
+    check_updates: &'a [Update],
+    installed_packages: &[NVR],
+    builds_for_update: &mut HashMap<String, Vec<String>>,
+) -> Result<Vec<&'a Update>, Error> {
+    let mut installed_matched: Vec<&Update> = Vec::new();
+
+    for update in check_updates {
+        let nvrs = update
+            .builds
+            .iter()
+            .map(|b| b.nvr.parse())
+            .collect::<Result<Vec<NVR>, Error>>()?;
+
+        for nvr in nvrs {
+            if installed_packages.contains(&nvr) {
+                installed_matched.push(update);
+
+                builds_for_update
+                    .entry(update.alias.clone())
+                    .and_modify(|e| e.push(nvr.to_string()))
+                    .or_insert_with(|| vec![nvr.to_string()]);
+            };
+        }
+    }
+
+    // deduplicate updates with multiple builds
+    installed_matched.sort_by(|a, b| a.alias.cmp(&b.alias));
+    installed_matched.dedup_by(|a, b| a.alias == b.alias);
+
+    Ok(installed_matched)
+}
+
+/// This helper function returns the time when the most recently installed binary package from the
+/// given builds was installed.
+pub fn install_time(builds: &[String], installed: &InstalledPackages) -> Option<DateTime<Utc>> {
+    builds
+        .iter()
+        .filter_map(|build| installed.src_bin_map.get(build))
+        .flatten()
+        .filter_map(|binary| installed.install_times.get(binary))
+        .max()
+        .copied()
+}
+
+/// This helper function removes updates that were installed less than `min_days` or more than
+/// `max_days` days ago, and returns the number of removed updates. Updates for which the
+/// installation time is not known are kept.
+pub fn filter_installed_days(
+    updates: &mut Vec<&Update>,
+    builds_for_update: &HashMap<String, Vec<String>>,
+    installed: &InstalledPackages,
+    min_days: Option<u32>,
+    max_days: Option<u32>,
+    now: DateTime<Utc>,
+) -> usize {
+    let before = updates.len();
+
+    updates.retain(|update| {
+        let builds = builds_for_update
+            .get(&update.alias)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let Some(installed) = install_time(builds, installed) else {
+            return true;
+        };
+
+        let age = now - installed;
+        let too_new = min_days.is_some_and(|days| age < Duration::days(days.into()));
+        let too_old = max_days.is_some_and(|days| age > Duration::days(days.into()));
+        !too_new && !too_old
+    });
+
+    before - updates.len()
+}
+
+/// This helper function returns all updates which contain builds that are only present in a staged
+/// deployment, but not in the booted one. These updates cannot be tested before rebooting.
+pub fn filter_staged_updates<'a>(
+    check_updates: &'a [Update],
+    installed_packages: &[NVR],
+    staged_packages: &[NVR],
+) -> Result<Vec<&'a Update>, Error> {
+    let mut staged_matched: Vec<&Update> = Vec::new();
+
+    for update in check_updates {
+        let nvrs = update
+            .builds
+            .iter()
+            .map(|b| b.nvr.parse())
+            .collect::<Result<Vec<NVR>, Error>>()?;
+
+        let staged = nvrs.iter().any(|nvr| staged_packages.contains(nvr));
+        let booted = nvrs.iter().any(|nvr| installed_packages.contains(nvr));
+
+        if staged && !booted {
+            staged_matched.push(update);
+        }
+    }
+
+    Ok(staged_matched)
+}
+
+/// This helper function selects installed updates that were not created by the current user,
+/// sorted by submission date.
+pub fn select_updates<'a>(
+    updates: &'a [Update],
+    username: &str,
+    installed_packages: &[NVR],
+    builds_for_update: &mut HashMap<String, Vec<String>>,
+) -> Result<Vec<&'a Update>, Error> {
+    let mut installed_updates = filter_installed_updates(updates, installed_packages, builds_for_update)?;
+
+    // filter out updates created by the current user
+    installed_updates.retain(|update| update.user.name != username);
+
+    // sort updates by submission date
+    installed_updates.sort_by(|a, b| a.date_submitted.cmp(&b.date_submitted));
+
+    Ok(installed_updates)
+}
+
+/// This helper function removes updates that are no longer installed from the list of ignored
+/// updates, and filters out updates that are ignored, or that exclusively contain permanently
+/// ignored packages.
+pub fn apply_ignore_lists(installed_updates: &mut Vec<&Update>, ignored: &mut IgnoreLists, check_ignored: bool) {
+    // remove old updates from ignored list
+    ignored
+        .ignored_updates
+        .retain(|i| installed_updates.iter().map(|u| &u.alias).any(|x| x == i));
+
+    // filter out updates that exclusively contain permanently ignored packages
+    installed_updates.retain(|update| {
+        let names = packages_in_update(update);
+        !names.iter().all(|name| ignored.ignored_packages.contains(name))
+    });
+
+    // filter out previously ignored updates
+    if !check_ignored {
+        installed_updates.retain(|update| !ignored.ignored_updates.contains(&update.alias));
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::sysinfo::{FixtureInventory, Inventory};
+
+    const INVENTORY: &str = include_str!("../tests/fixtures/inventory.json");
+    const UPDATES: &str = include_str!("../tests/fixtures/updates.json");
+
+    async fn fixtures() -> (Vec<Update>, Vec<NVR>) {
+        let updates: Vec<Update> = serde_json::from_str(UPDATES).unwrap();
+        let inventory = FixtureInventory::from_json(INVENTORY).unwrap();
+        let installed = inventory.get_installed().await.unwrap();
+        (updates, installed)
+    }
+
+    fn aliases(updates: &[&Update]) -> Vec<String> {
+        updates.iter().map(|update| update.alias.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn select() {
+        let (updates, installed) = fixtures().await;
+        let mut builds_for_update = HashMap::new();
+
+        let selected = select_updates(&updates, "tester", &installed, &mut builds_for_update).unwrap();
+
+        // own updates and updates with builds that are not installed are filtered out,
+        // and the remaining updates are sorted by submission date
+        assert_eq!(
+            aliases(&selected),
+            vec![
+                "FEDORA-2024-0003cccc",
+                "FEDORA-2024-0005eeee",
+                "FEDORA-2024-0006ffff",
+                "FEDORA-2024-0001aaaa",
+            ]
+        );
+
+        assert_eq!(
+            builds_for_update.get("FEDORA-2024-0003cccc").unwrap(),
+            &vec!["qux-1.0-1.fc40", "quux-1.0-1.fc40"]
+        );
+        assert!(!builds_for_update.contains_key("FEDORA-2024-0004dddd"));
+    }
+
+    #[tokio::test]
+    async fn partial() {
+        let (updates, installed) = fixtures().await;
+
+        // all builds are installed
+        assert!(missing_builds(&updates[0], &installed).unwrap().is_empty());
+
+        // a different version of the package is installed
+        assert_eq!(
+            missing_builds(&updates[3], &installed).unwrap(),
+            vec![MissingBuild {
+                nvr: String::from("notinstalled-1.0-1.fc40"),
+                installed: Some(String::from("notinstalled-0.9-1.fc40")),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn staged() {
+        let (updates, installed) = fixtures().await;
+        let staged = FixtureInventory::from_json(INVENTORY)
+            .unwrap()
+            .get_staged()
+            .await
+            .unwrap();
+
+        // updates with builds in the booted deployment are not reported, even if other builds
+        // from the same update are only in the staged deployment
+        let matched = filter_staged_updates(&updates, &installed, &staged).unwrap();
+        assert_eq!(aliases(&matched), vec!["FEDORA-2024-0004dddd"]);
+    }
+
+    #[tokio::test]
+    async fn ignore_lists() {
+        let (updates, installed) = fixtures().await;
+        let mut builds_for_update = HashMap::new();
+        let selected = select_updates(&updates, "tester", &installed, &mut builds_for_update).unwrap();
+
+        let mut ignored = IgnoreLists {
+            ignored_updates: vec![
+                String::from("FEDORA-2024-0006ffff"),
+                String::from("FEDORA-2023-00000000"),
+            ],
+            ignored_packages: vec![String::from("blocked")],
+        };
+
+        let mut filtered = selected.clone();
+        apply_ignore_lists(&mut filtered, &mut ignored, false);
+
+        assert_eq!(aliases(&filtered), vec!["FEDORA-2024-0003cccc", "FEDORA-2024-0001aaaa"]);
+        // updates that are no longer installed are removed from the ignore list
+        assert_eq!(ignored.ignored_updates, vec!["FEDORA-2024-0006ffff"]);
+
+        let mut filtered = selected.clone();
+        apply_ignore_lists(&mut filtered, &mut ignored, true);
+
+        assert_eq!(
+            aliases(&filtered),
+            vec!["FEDORA-2024-0003cccc", "FEDORA-2024-0006ffff", "FEDORA-2024-0001aaaa"]
+        );
+    }
+
+    #[tokio::test]
+    async fn installed_days() {
+        let (updates, installed) = fixtures().await;
+        let packages = InstalledPackages::collect(&FixtureInventory::from_json(INVENTORY).unwrap())
+            .await
+            .unwrap();
+        let mut builds_for_update = HashMap::new();
+        let mut selected = select_updates(&updates, "tester", &installed, &mut builds_for_update).unwrap();
+
+        let now: DateTime<Utc> = "2024-05-06T10:00:00Z".parse().unwrap();
+        let removed = filter_installed_days(&mut selected, &builds_for_update, &packages, Some(2), Some(30), now);
+
+        // foo was installed one day ago, and qux and quux were installed more than a month ago
+        assert_eq!(removed, 2);
+        assert_eq!(aliases(&selected), vec!["FEDORA-2024-0005eeee", "FEDORA-2024-0006ffff"]);
+    }
+}
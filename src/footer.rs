@@ -0,0 +1,135 @@
+use std::env;
+
+use crate::sysinfo::{InstalledPackages, RunningSystem};
+
+/// This struct contains a compact description of the current system, which can be appended to
+/// comments, so maintainers do not need to ask testers which hardware or desktop they are using.
+#[derive(Debug)]
+pub struct SystemSummary {
+    /// Fedora release, for example, "F40"
+    pub release: String,
+    /// CPU architecture, for example, "x86_64"
+    pub arch: String,
+    /// release of the running kernel
+    pub kernel: Option<String>,
+    /// desktop environment and session type, for example, "GNOME (wayland)"
+    pub desktop: Option<String>,
+    /// vendor and model of the hardware
+    pub hardware: Option<String>,
+}
+
+impl SystemSummary {
+    /// This method collects a description of the current system.
+    pub fn new(installed: &InstalledPackages, running: &RunningSystem) -> SystemSummary {
+        SystemSummary {
+            release: installed.release.to_string(),
+            arch: env::consts::ARCH.to_string(),
+            kernel: running.kernel.clone(),
+            desktop: desktop_session(
+                env::var("XDG_CURRENT_DESKTOP").ok().as_deref(),
+                env::var("XDG_SESSION_TYPE").ok().as_deref(),
+            ),
+            hardware: running.hardware.clone(),
+        }
+    }
+}
+
+/// This helper function describes the desktop session from the values of the
+/// `XDG_CURRENT_DESKTOP` and `XDG_SESSION_TYPE` environment variables.
+fn desktop_session(desktop: Option<&str>, session_type: Option<&str>) -> Option<String> {
+    let desktop = desktop.filter(|value| !value.is_empty());
+    let session_type = session_type.filter(|value| !value.is_empty() && *value != "tty");
+
+    match (desktop, session_type) {
+        (Some(desktop), Some(session_type)) => Some(format!("{} ({})", desktop, session_type)),
+        (Some(desktop), None) => Some(desktop.to_string()),
+        (None, Some(session_type)) => Some(session_type.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// This function formats a description of the current system and the installed packages from an
+/// update as a footer for comments.
+pub fn format_footer(summary: &SystemSummary, packages: &[&str]) -> String {
+    let mut system = vec![format!("{} ({})", summary.release, summary.arch)];
+
+    if let Some(kernel) = &summary.kernel {
+        system.push(format!("kernel {}", kernel));
+    }
+    if let Some(desktop) = &summary.desktop {
+        system.push(desktop.clone());
+    }
+    if let Some(hardware) = &summary.hardware {
+        system.push(hardware.clone());
+    }
+
+    let mut lines = vec![String::from("---"), format!("System: {}", system.join(", "))];
+    if !packages.is_empty() {
+        lines.push(format!("Installed: {}", packages.join(", ")));
+    }
+
+    lines.join("\n")
+}
+
+/// This function appends a footer to a comment. If there is no comment, the footer is used as the
+/// comment.
+pub fn append_footer(comment: Option<String>, footer: &str) -> Option<String> {
+    match comment {
+        Some(comment) => Some(format!("{}\n\n{}\n", comment.trim_end(), footer)),
+        None => Some(format!("{}\n", footer)),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn summary() -> SystemSummary {
+        SystemSummary {
+            release: String::from("F40"),
+            arch: String::from("x86_64"),
+            kernel: Some(String::from("6.8.9-300.fc40.x86_64")),
+            desktop: Some(String::from("GNOME (wayland)")),
+            hardware: Some(String::from("LENOVO 21AH00BSGE")),
+        }
+    }
+
+    #[test]
+    fn footer() {
+        assert_eq!(
+            format_footer(&summary(), &["foo-1.2-1.fc40.x86_64", "foo-libs-1.2-1.fc40.x86_64"]),
+            "---\n\
+             System: F40 (x86_64), kernel 6.8.9-300.fc40.x86_64, GNOME (wayland), LENOVO 21AH00BSGE\n\
+             Installed: foo-1.2-1.fc40.x86_64, foo-libs-1.2-1.fc40.x86_64"
+        );
+
+        let minimal = SystemSummary {
+            kernel: None,
+            desktop: None,
+            hardware: None,
+            ..summary()
+        };
+        assert_eq!(format_footer(&minimal, &[]), "---\nSystem: F40 (x86_64)");
+    }
+
+    #[test]
+    fn append() {
+        assert_eq!(
+            append_footer(Some(String::from("Works fine.\n")), "---").unwrap(),
+            "Works fine.\n\n---\n"
+        );
+        assert_eq!(append_footer(None, "---").unwrap(), "---\n");
+    }
+
+    #[test]
+    fn desktop() {
+        assert_eq!(
+            desktop_session(Some("KDE"), Some("wayland")).as_deref(),
+            Some("KDE (wayland)")
+        );
+        assert_eq!(desktop_session(Some("GNOME"), Some("tty")).as_deref(), Some("GNOME"));
+        assert_eq!(desktop_session(None, Some("tty")), None);
+        assert_eq!(desktop_session(Some(""), None), None);
+    }
+}
@@ -0,0 +1,602 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bodhi::{BodhiDate, Comment, Karma, Update};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs::read_to_string;
+use tokio::fs::write;
+
+use crate::error::Error;
+use crate::nvr::NVR;
+
+// name of the user that bodhi uses for automatic comments (for example, when an update is edited)
+const BODHI_USER: &str = "bodhi";
+
+const FILE_NAME: &str = "fedora-update-feedback.snapshots";
+
+// snapshots are kept for this many days, which is much longer than updates usually stay in testing
+const SNAPSHOT_MAX_AGE: i64 = 365;
+// tolerated difference between the local clock and the clock of the bodhi server
+const CLOCK_SKEW_MINUTES: i64 = 10;
+
+/// This enum describes the feedback that a user has previously provided for an update.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackStatus {
+    /// the user has never commented on the update
+    NeverCommented,
+    /// the user has commented on the update (possibly with bug or test case feedback), but without
+    /// providing karma
+    CommentedWithoutKarma,
+    /// the user has provided karma for the builds that are currently part of the update
+    KarmaForCurrentBuilds,
+    /// the user has provided karma, but builds have been added to or removed from the update
+    /// since then, which causes bodhi to reset karma
+    KarmaForSupersededBuilds,
+    /// the user has provided karma, and the update has been edited since then, but the edit history
+    /// could not be parsed, so karma might have been reset
+    KarmaMaybeReset,
+}
+
+impl FeedbackStatus {
+    /// This method returns whether the user has previously commented on the update at all.
+    pub fn has_commented(self) -> bool {
+        self != FeedbackStatus::NeverCommented
+    }
+
+    /// This method returns whether previously provided feedback still counts for the update in its
+    /// current state. Updates with current feedback are skipped by default.
+    pub fn is_current(self) -> bool {
+        self == FeedbackStatus::KarmaForCurrentBuilds
+    }
+}
+
+/// This struct represents an edit of an update which added or removed builds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BuildChange {
+    /// date & time of the edit
+    pub timestamp: BodhiDate,
+    /// NVRs of builds that were added to the update
+    pub added: Vec<String>,
+    /// NVRs of builds that were removed from the update
+    pub removed: Vec<String>,
+}
+
+/// This helper function extracts the lists of added and removed builds from the comment that bodhi
+/// posts when an update is edited.
+///
+/// Only the structure of the comment is relevant: build NVRs are listed as list items below a
+/// header that mentions either new / added or removed builds.
+fn parse_build_change(comment: &Comment) -> Option<BuildChange> {
+    enum Section {
+        Added,
+        Removed,
+        Other,
+    }
+
+    if comment.user.name != BODHI_USER {
+        return None;
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut section = Section::Other;
+
+    for line in comment.text.lines().map(str::trim) {
+        if let Some(item) = line.strip_prefix("- ") {
+            // ignore list items that are not valid NVRs (for example, bugs or test cases)
+            let nvr = item.split_whitespace().next().unwrap_or_default();
+            if nvr.parse::<NVR>().is_err() {
+                continue;
+            }
+
+            match section {
+                Section::Added => added.push(nvr.to_string()),
+                Section::Removed => removed.push(nvr.to_string()),
+                Section::Other => {},
+            }
+        } else if !line.is_empty() {
+            let header = line.to_lowercase();
+
+            section = if !header.ends_with(':') || !header.contains("build") {
+                Section::Other
+            } else if header.contains("removed") {
+                Section::Removed
+            } else if header.contains("new") || header.contains("added") {
+                Section::Added
+            } else {
+                Section::Other
+            };
+        }
+    }
+
+    if added.is_empty() && removed.is_empty() {
+        None
+    } else {
+        Some(BuildChange {
+            timestamp: comment.timestamp.clone(),
+            added,
+            removed,
+        })
+    }
+}
+
+/// This helper function determines whether an automatic comment mentions changed builds or a karma
+/// reset, but could not be parsed, for example, because bodhi changed the format of its comments.
+fn is_unrecognized_edit(comment: &Comment) -> bool {
+    if comment.user.name != BODHI_USER || parse_build_change(comment).is_some() {
+        return false;
+    }
+
+    let text = comment.text.to_lowercase();
+    text.contains("build") || (text.contains("karma") && text.contains("reset"))
+}
+
+/// This function returns all edits of an update that added or removed builds, sorted by date.
+pub fn build_changes(update: &Update) -> Vec<BuildChange> {
+    let mut changes: Vec<BuildChange> = update
+        .comments
+        .iter()
+        .flatten()
+        .filter_map(parse_build_change)
+        .collect();
+
+    changes.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    changes
+}
+
+/// This function reconstructs the list of builds that were part of an update at a specific point
+/// in time, by reverting all edits that happened after it. The result is sorted.
+pub fn builds_at(update: &Update, timestamp: &BodhiDate) -> Vec<String> {
+    let mut builds: Vec<String> = update.builds.iter().map(|build| build.nvr.clone()).collect();
+
+    for change in build_changes(update).iter().rev() {
+        if &change.timestamp <= timestamp {
+            break;
+        }
+
+        builds.retain(|build| !change.added.contains(build));
+        for build in &change.removed {
+            if !builds.contains(build) {
+                builds.push(build.clone());
+            }
+        }
+    }
+
+    builds.sort();
+    builds
+}
+
+/// This function returns the most recent comment that a user has submitted for an update.
+pub fn last_comment<'a>(update: &'a Update, user: &str) -> Option<&'a Comment> {
+    update
+        .comments
+        .iter()
+        .flatten()
+        .filter(|comment| comment.user.name == user)
+        .max_by(|a, b| a.timestamp.cmp(&b.timestamp))
+}
+
+/// This function determines which feedback a user has previously provided for an update, and
+/// whether any karma they provided still applies to the builds that are currently part of it.
+pub fn feedback_status(update: &Update, user: &str) -> FeedbackStatus {
+    let comments: Vec<&Comment> = update
+        .comments
+        .iter()
+        .flatten()
+        .filter(|comment| comment.user.name == user)
+        .collect();
+
+    if comments.is_empty() {
+        return FeedbackStatus::NeverCommented;
+    }
+
+    // bodhi only takes the most recent karma of every user into account
+    let karma = comments
+        .iter()
+        .filter(|comment| comment.karma != Karma::Neutral)
+        .max_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    let Some(karma) = karma else {
+        return FeedbackStatus::CommentedWithoutKarma;
+    };
+
+    // builds cannot have changed if the update was not edited after karma was provided
+    let edited = update
+        .date_modified
+        .as_ref()
+        .is_some_and(|modified| modified > &karma.timestamp);
+
+    if !edited {
+        return FeedbackStatus::KarmaForCurrentBuilds;
+    }
+
+    let mut current: Vec<String> = update.builds.iter().map(|build| build.nvr.clone()).collect();
+    current.sort();
+
+    if builds_at(update, &karma.timestamp) != current {
+        return FeedbackStatus::KarmaForSupersededBuilds;
+    }
+
+    // builds look unchanged, but edits that could not be parsed might have changed them
+    let unrecognized = update
+        .comments
+        .iter()
+        .flatten()
+        .any(|comment| comment.timestamp > karma.timestamp && is_unrecognized_edit(comment));
+
+    if unrecognized {
+        FeedbackStatus::KarmaMaybeReset
+    } else {
+        FeedbackStatus::KarmaForCurrentBuilds
+    }
+}
+
+/// This struct contains the state of an update at the time when feedback for it was submitted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UpdateSnapshot {
+    /// date & time when the feedback was submitted
+    pub submitted: DateTime<Utc>,
+    /// NVRs of builds that were part of the update
+    pub builds: Vec<String>,
+    /// update notes
+    pub notes: String,
+    /// IDs of bugs that were associated with the update
+    pub bugs: Vec<u32>,
+    /// names of test cases that were associated with the update
+    pub test_cases: Vec<String>,
+}
+
+impl UpdateSnapshot {
+    /// This method records the current state of an update.
+    pub fn of(update: &Update) -> UpdateSnapshot {
+        let mut builds: Vec<String> = update.builds.iter().map(|build| build.nvr.clone()).collect();
+        builds.sort();
+
+        UpdateSnapshot {
+            submitted: Utc::now(),
+            builds,
+            notes: update.notes.clone(),
+            bugs: update.bugs.iter().map(|bug| bug.bug_id).collect(),
+            test_cases: update
+                .test_cases
+                .iter()
+                .flatten()
+                .map(|test_case| test_case.name.clone())
+                .collect(),
+        }
+    }
+}
+
+fn get_snapshots_path() -> Result<PathBuf, Error> {
+    let data_dir = dirs::data_dir().ok_or_else(|| Error::config("Unable to determine the data directory."))?;
+    Ok(data_dir.join(FILE_NAME))
+}
+
+/// Helper function to get the snapshots of updates at the time feedback was submitted from the
+/// data file.
+pub async fn get_snapshots() -> Result<HashMap<String, UpdateSnapshot>, Error> {
+    let snapshots_path = get_snapshots_path()?;
+
+    let string = match read_to_string(&snapshots_path).await {
+        Ok(string) => string,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(error) => return Err(Error::io(format!("Failed to read {}", snapshots_path.display()), error)),
+    };
+
+    serde_json::from_str(&string)
+        .map_err(|error| Error::parse_with(format!("Failed to parse {}", snapshots_path.display()), error))
+}
+
+/// Helper function to add the snapshot of an update to the data file (replacing any previous
+/// snapshot of the same update), and to remove old snapshots.
+pub async fn save_snapshot(alias: &str, snapshot: UpdateSnapshot) -> Result<(), Error> {
+    let snapshots_path = get_snapshots_path()?;
+
+    let mut snapshots = get_snapshots().await?;
+    snapshots.insert(alias.to_string(), snapshot);
+
+    let cutoff = Utc::now() - Duration::days(SNAPSHOT_MAX_AGE);
+    snapshots.retain(|_, snapshot| snapshot.submitted > cutoff);
+
+    let contents = serde_json::to_string_pretty(&snapshots)
+        .map_err(|error| Error::parse_with("Failed to serialize update snapshots", error))?;
+    write(&snapshots_path, contents)
+        .await
+        .map_err(|error| Error::io(format!("Failed to write {}", snapshots_path.display()), error))?;
+
+    Ok(())
+}
+
+/// This struct describes how an update has changed since a user last commented on it.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct UpdateChanges {
+    /// NVRs of builds that were added to the update
+    pub added_builds: Vec<String>,
+    /// NVRs of builds that were removed from the update
+    pub removed_builds: Vec<String>,
+    /// NVRs of builds that were replaced with a different build of the same package (old, new)
+    pub replaced_builds: Vec<(String, String)>,
+    /// previous update notes, if the notes have been changed (only known if feedback was submitted
+    /// with `fedora-update-feedback`)
+    pub previous_notes: Option<String>,
+    /// IDs of bugs that were associated with the update since then
+    pub new_bugs: Vec<u32>,
+    /// names of test cases that were associated with the update since then
+    pub new_test_cases: Vec<String>,
+}
+
+impl UpdateChanges {
+    /// This method returns whether no changes were detected.
+    pub fn is_empty(&self) -> bool {
+        self == &UpdateChanges::default()
+    }
+}
+
+/// This helper function pairs removed and added builds of the same package as replaced builds.
+fn pair_replaced(changes: &mut UpdateChanges) {
+    let name = |nvr: &str| nvr.parse::<NVR>().map(|nvr| nvr.n).ok();
+
+    let mut removed = Vec::new();
+    for old in std::mem::take(&mut changes.removed_builds) {
+        let position = changes
+            .added_builds
+            .iter()
+            .position(|new| name(new).is_some() && name(new) == name(&old));
+
+        match position {
+            Some(position) => {
+                let new = changes.added_builds.remove(position);
+                changes.replaced_builds.push((old, new));
+            },
+            None => removed.push(old),
+        }
+    }
+
+    changes.removed_builds = removed;
+}
+
+/// This function determines what has changed in an update since the user last commented on it.
+///
+/// If the feedback was submitted with `fedora-update-feedback`, the snapshot of the update that was
+/// recorded at that time is used. Otherwise, the builds at the time of the comment are
+/// reconstructed from the edit history of the update, and new bugs and test cases are determined
+/// from the bug and test case feedback that was included in the comment. Changes to update notes
+/// can only be detected with a snapshot.
+///
+/// Returns `None` if the user has never commented on the update.
+pub fn changes_since_last_comment(
+    update: &Update,
+    user: &str,
+    snapshot: Option<&UpdateSnapshot>,
+) -> Option<UpdateChanges> {
+    let comment = last_comment(update, user)?;
+
+    // snapshots are not valid if feedback was submitted again in some other way afterwards
+    let skew = Duration::minutes(CLOCK_SKEW_MINUTES);
+    let snapshot = snapshot.filter(|snapshot| BodhiDate::from(snapshot.submitted + skew) >= comment.timestamp);
+
+    let mut current: Vec<String> = update.builds.iter().map(|build| build.nvr.clone()).collect();
+    current.sort();
+
+    let previous = match snapshot {
+        Some(snapshot) => snapshot.builds.clone(),
+        None => builds_at(update, &comment.timestamp),
+    };
+
+    let mut changes = UpdateChanges {
+        added_builds: current.iter().filter(|nvr| !previous.contains(nvr)).cloned().collect(),
+        removed_builds: previous.iter().filter(|nvr| !current.contains(nvr)).cloned().collect(),
+        ..Default::default()
+    };
+    pair_replaced(&mut changes);
+
+    let (bugs, test_cases): (Vec<u32>, Vec<String>) = match snapshot {
+        Some(snapshot) => {
+            if snapshot.notes.trim() != update.notes.trim() {
+                changes.previous_notes = Some(snapshot.notes.clone());
+            }
+            (snapshot.bugs.clone(), snapshot.test_cases.clone())
+        },
+        None => (
+            comment.bug_feedback.iter().map(|feedback| feedback.bug_id).collect(),
+            comment
+                .testcase_feedback
+                .iter()
+                .map(|feedback| feedback.testcase.name.clone())
+                .collect(),
+        ),
+    };
+
+    // without a snapshot, new bugs and test cases can only be detected if the comment contained
+    // feedback for the ones that were associated with the update at the time
+    if snapshot.is_some() || !bugs.is_empty() {
+        changes.new_bugs = update
+            .bugs
+            .iter()
+            .map(|bug| bug.bug_id)
+            .filter(|id| !bugs.contains(id))
+            .collect();
+    }
+    if snapshot.is_some() || !test_cases.is_empty() {
+        changes.new_test_cases = update
+            .test_cases
+            .iter()
+            .flatten()
+            .map(|test_case| test_case.name.clone())
+            .filter(|name| !test_cases.contains(name))
+            .collect();
+    }
+
+    Some(changes)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use serde_json::{json, Value};
+
+    const UPDATES: &str = include_str!("../tests/fixtures/updates.json");
+
+    fn updates() -> Vec<Value> {
+        serde_json::from_str(UPDATES).unwrap()
+    }
+
+    fn update(value: Value) -> Update {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn changes() {
+        let foo = update(updates().remove(0));
+
+        let changes = build_changes(&foo);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].added, vec!["foo-1.2-1.fc40"]);
+        assert_eq!(changes[0].removed, vec!["foo-1.1-1.fc40"]);
+
+        assert_eq!(
+            builds_at(&foo, &"2024-05-03 08:00:00".parse().unwrap()),
+            vec!["foo-1.1-1.fc40"]
+        );
+        assert_eq!(
+            builds_at(&foo, &"2024-05-04 09:00:00".parse().unwrap()),
+            vec!["foo-1.2-1.fc40"]
+        );
+    }
+
+    #[test]
+    fn status() {
+        let updates = updates();
+
+        // builds were replaced after karma was provided
+        let foo = update(updates[0].clone());
+        assert_eq!(
+            feedback_status(&foo, "tester"),
+            FeedbackStatus::KarmaForSupersededBuilds
+        );
+        assert_eq!(feedback_status(&foo, "bob"), FeedbackStatus::NeverCommented);
+
+        // update was never edited after karma was provided
+        let qux = update(updates[2].clone());
+        assert_eq!(feedback_status(&qux, "tester"), FeedbackStatus::KarmaForCurrentBuilds);
+        assert_eq!(
+            feedback_status(&update(updates[3].clone()), "tester"),
+            FeedbackStatus::NeverCommented
+        );
+    }
+
+    #[test]
+    fn status_edits_without_build_changes() {
+        let mut qux = updates()[2].clone();
+        qux["date_modified"] = json!("2024-04-05 10:00:00");
+
+        let mut edit = qux["comments"][0].clone();
+        edit["user"]["name"] = json!("bodhi");
+        edit["karma"] = json!(0);
+        edit["timestamp"] = json!("2024-04-05 10:00:00");
+        edit["text"] = json!("bob edited this update. ");
+        qux["comments"].as_array_mut().unwrap().push(edit);
+
+        // only the update notes were edited, so karma still applies
+        assert_eq!(
+            feedback_status(&update(qux), "tester"),
+            FeedbackStatus::KarmaForCurrentBuilds
+        );
+    }
+
+    #[test]
+    fn status_unrecognized_edit() {
+        let mut qux = updates()[2].clone();
+        qux["date_modified"] = json!("2024-04-05 10:00:00");
+
+        let mut edit = qux["comments"][0].clone();
+        edit["user"]["name"] = json!("bodhi");
+        edit["karma"] = json!(0);
+        edit["timestamp"] = json!("2024-04-05 10:00:00");
+        edit["text"] =
+            json!("bob edited this update. Builds: qux-2.1-1.fc40 (was qux-2.0-1.fc40). Karma has been reset.");
+        qux["comments"].as_array_mut().unwrap().push(edit);
+
+        // the edit comment could not be parsed, so the update is not skipped
+        let status = feedback_status(&update(qux), "tester");
+        assert_eq!(status, FeedbackStatus::KarmaMaybeReset);
+        assert!(status.has_commented());
+        assert!(!status.is_current());
+    }
+
+    #[test]
+    fn status_without_karma() {
+        let mut qux = updates()[2].clone();
+        qux["comments"][0]["karma"] = json!(0);
+
+        let qux = update(qux);
+        assert_eq!(feedback_status(&qux, "tester"), FeedbackStatus::CommentedWithoutKarma);
+        assert_eq!(last_comment(&qux, "tester").unwrap().text, "LGTM");
+    }
+
+    #[test]
+    fn reworded_reset() {
+        let mut foo = updates()[0].clone();
+        foo["comments"][2]["text"] = json!(
+            "Builds were changed by alice.\n\nAdded builds:\n- foo-1.2-1.fc40\n\nRemoved builds:\n- foo-1.1-1.fc40\n"
+        );
+
+        // detection does not depend on the exact wording of the comment
+        assert_eq!(
+            feedback_status(&update(foo), "tester"),
+            FeedbackStatus::KarmaForSupersededBuilds
+        );
+    }
+
+    #[test]
+    fn changes_from_history() {
+        let foo = update(updates().remove(0));
+
+        let changes = changes_since_last_comment(&foo, "tester", None).unwrap();
+        assert_eq!(
+            changes.replaced_builds,
+            vec![(String::from("foo-1.1-1.fc40"), String::from("foo-1.2-1.fc40"))]
+        );
+        assert!(changes.added_builds.is_empty());
+        assert!(changes.removed_builds.is_empty());
+        // the comment did not contain bug or test case feedback, so nothing is known about them
+        assert!(changes.new_bugs.is_empty());
+        assert!(changes.new_test_cases.is_empty());
+        assert_eq!(changes.previous_notes, None);
+
+        assert_eq!(changes_since_last_comment(&foo, "bob", None), None);
+    }
+
+    #[test]
+    fn changes_from_snapshot() {
+        let foo = update(updates().remove(0));
+
+        let snapshot = UpdateSnapshot {
+            submitted: "2024-05-03T08:00:00Z".parse().unwrap(),
+            builds: vec![String::from("foo-1.1-1.fc40"), String::from("libfoo-1.0-1.fc40")],
+            notes: String::from("Update to 1.1."),
+            bugs: Vec::new(),
+            test_cases: vec![String::from("QA:Testcase_foo")],
+        };
+
+        let changes = changes_since_last_comment(&foo, "tester", Some(&snapshot)).unwrap();
+        assert_eq!(changes.replaced_builds.len(), 1);
+        assert_eq!(changes.removed_builds, vec!["libfoo-1.0-1.fc40"]);
+        assert_eq!(changes.previous_notes.as_deref(), Some("Update to 1.1."));
+        assert_eq!(changes.new_bugs, vec![2000001]);
+        assert!(changes.new_test_cases.is_empty());
+
+        // snapshots from before the last comment are not used
+        let stale = UpdateSnapshot {
+            submitted: "2024-05-01T08:00:00Z".parse().unwrap(),
+            ..snapshot
+        };
+        let changes = changes_since_last_comment(&foo, "tester", Some(&stale)).unwrap();
+        assert!(changes.removed_builds.is_empty());
+        assert_eq!(changes.previous_notes, None);
+    }
+}
@@ -4,12 +4,17 @@ use serde::{Deserialize, Serialize};
 use tokio::fs::read_to_string;
 use tokio::fs::write;
 
+use crate::error::Error;
+
 const CACHE_ERROR: &str = "Failed to get cache directory.";
 const FILE_NAME: &str = "fedora-update-feedback.ignored";
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+/// This struct contains the lists of updates and packages that are ignored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct IgnoreLists {
+    /// aliases of ignored updates
     pub ignored_updates: Vec<String>,
+    /// names of packages that are permanently ignored
     pub ignored_packages: Vec<String>,
 }
 
@@ -19,10 +24,12 @@ fn get_ignore_path() -> PathBuf {
 }
 
 /// Helper function to get list of ignored updates from the cache file.
-pub async fn get_ignored() -> Result<IgnoreLists, String> {
+pub async fn get_ignored() -> Result<IgnoreLists, Error> {
     let ignore_path = get_ignore_path();
 
-    let string = read_to_string(ignore_path).await.map_err(|error| error.to_string())?;
+    let string = read_to_string(&ignore_path)
+        .await
+        .map_err(|error| Error::io(format!("Failed to read {}", ignore_path.display()), error))?;
 
     // attempt to parse new JSON format
     let contents: IgnoreLists = match serde_json::from_str(&string) {
@@ -38,11 +45,14 @@ pub async fn get_ignored() -> Result<IgnoreLists, String> {
 }
 
 /// Helper function to write the list of ignored updates to the cache file.
-pub async fn set_ignored(ignored: &IgnoreLists) -> Result<(), String> {
+pub async fn set_ignored(ignored: &IgnoreLists) -> Result<(), Error> {
     let ignore_path = get_ignore_path();
 
-    let contents = serde_json::to_string_pretty(ignored).map_err(|error| error.to_string())?;
-    write(ignore_path, contents).await.map_err(|error| error.to_string())?;
+    let contents = serde_json::to_string_pretty(ignored)
+        .map_err(|error| Error::parse_with("Failed to serialize ignored updates", error))?;
+    write(&ignore_path, contents)
+        .await
+        .map_err(|error| Error::io(format!("Failed to write {}", ignore_path.display()), error))?;
 
     Ok(())
 }
@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::error::Error;
+
+const FILE_NAME: &str = "fedora-update-feedback.history";
+
+// number of entries that are kept in the prompt history
+const HISTORY_SIZE: usize = 1000;
+
+/// This helper function returns the path of the file that contains the prompt history.
+fn history_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+/// This helper function converts errors from reading lines into I/O errors.
+fn readline_error(error: ReadlineError) -> Error {
+    let source = match error {
+        ReadlineError::Io(error) => error,
+        ReadlineError::Eof => std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "standard input was closed"),
+        error => std::io::Error::other(error.to_string()),
+    };
+    Error::io("Failed to read from stdin", source)
+}
+
+/// This struct provides tab completion for the values that are accepted by the current prompt.
+#[derive(Debug, Default)]
+struct Completions {
+    candidates: Vec<String>,
+}
+
+impl Completer for Completions {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let prefix = &line[..pos];
+        let matches = self
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .cloned()
+            .collect();
+        Ok((0, matches))
+    }
+}
+
+impl Hinter for Completions {
+    type Hint = String;
+}
+
+impl Highlighter for Completions {}
+
+impl Validator for Completions {}
+
+impl Helper for Completions {}
+
+/// This struct wraps a line editor that is used for answering prompts and writing comments without
+/// leaving the terminal. Answers to prompts (but not comments) are saved in a persistent prompt
+/// history.
+pub struct InlineEditor {
+    editor: Editor<Completions, FileHistory>,
+    history: Option<PathBuf>,
+}
+
+impl InlineEditor {
+    /// This method initializes the line editor and loads the prompt history from previous sessions.
+    pub fn new() -> Result<InlineEditor, Error> {
+        let config = rustyline::Config::builder()
+            .max_history_size(HISTORY_SIZE)
+            .map_err(readline_error)?
+            .history_ignore_dups(true)
+            .map_err(readline_error)?
+            .auto_add_history(false)
+            .build();
+
+        let mut editor: Editor<Completions, FileHistory> = Editor::with_config(config).map_err(readline_error)?;
+        editor.set_helper(Some(Completions::default()));
+
+        let history = history_path();
+        if let Some(path) = &history {
+            // the history file does not exist yet when the inline editor is used for the first time
+            let _ = editor.load_history(path);
+        }
+
+        Ok(InlineEditor { editor, history })
+    }
+
+    /// This helper method adds an answer to the prompt history.
+    fn add_history(&mut self, line: &str) -> Result<(), ReadlineError> {
+        if !line.trim().is_empty() {
+            self.editor.add_history_entry(line)?;
+            if let Some(path) = &self.history {
+                // failing to save the history should not interrupt providing feedback
+                let _ = self.editor.append_history(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This method prints a prompt and reads a value, which can be completed from the given list of
+    /// accepted values with the tab key.
+    pub fn read_line(&mut self, prompt: &str, completions: &[&str]) -> Result<String, Error> {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.candidates = completions.iter().map(|value| value.to_string()).collect();
+        }
+
+        let line = self.editor.readline(&format!("{}: ", prompt)).map_err(readline_error)?;
+        self.add_history(&line).map_err(readline_error)?;
+
+        Ok(line.trim().to_string())
+    }
+
+    /// This method reads a comment that can span multiple lines. Two empty lines or EOF (`Ctrl-D`)
+    /// end comment input, and `Ctrl-C` discards the comment.
+    ///
+    /// Lines of the initial text (for example, from a comment template) are pre-filled and can be
+    /// edited one by one. Comment lines are not saved in the prompt history, since they can contain
+    /// private notes.
+    pub fn read_comment(&mut self, initial: &str) -> Result<Option<String>, Error> {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.candidates.clear();
+        }
+
+        println!("Comment (end with two empty lines or Ctrl-D, discard with Ctrl-C):");
+
+        let mut initial = initial.lines();
+        let mut lines: Vec<String> = Vec::new();
+        let mut empty = 0;
+
+        loop {
+            match self
+                .editor
+                .readline_with_initial("> ", (initial.next().unwrap_or_default(), ""))
+            {
+                Ok(line) => {
+                    if line.trim().is_empty() {
+                        empty += 1;
+                        if empty == 2 {
+                            break;
+                        }
+                    } else {
+                        empty = 0;
+                    }
+                    lines.push(line);
+                },
+                Err(ReadlineError::Eof) => break,
+                Err(ReadlineError::Interrupted) => {
+                    println!("Comment discarded.");
+                    return Ok(None);
+                },
+                Err(error) => return Err(readline_error(error)),
+            }
+        }
+
+        let comment = lines.join("\n");
+        match comment.trim() {
+            "" => Ok(None),
+            comment => Ok(Some(format!("{}\n", comment))),
+        }
+    }
+}
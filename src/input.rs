@@ -1,45 +1,142 @@
-use std::collections::HashMap;
 use std::env;
 use std::io::{stdin, stdout, Write};
 
 use bodhi::{Karma, Update};
-use chrono::{DateTime, Utc};
+use tokio::fs::{read_to_string, write};
 use tokio::process::Command;
 
-use crate::output::print_update;
+use crate::drafts::{get_draft, prepare_draft, remove_draft};
+use crate::editor::{format_buffer, parse_buffer, EditedFeedback};
+use crate::error::Error;
+use crate::history::{FeedbackStatus, UpdateChanges};
+use crate::inline::InlineEditor;
+use crate::instance::BodhiInstance;
+use crate::output::{print_changes, print_update, proper_plural, UpdateDetails};
+use crate::template::RenderedTemplate;
 
 const DEFAULT_EDITOR: &str = "nano";
 
-fn detect_editor() -> String {
-    if let Ok(editor) = env::var("EDITOR") {
-        editor
-    } else if let Ok(editor) = env::var("VISUAL") {
-        editor
-    } else {
-        DEFAULT_EDITOR.to_string()
+// values that are accepted by karma prompts (used for tab completion)
+const KARMA_VALUES: &[&str] = &["+1", "0", "-1"];
+
+// actions that are accepted by the action prompt (used for tab completion)
+const ACTIONS: &[&str] = &["skip", "ignore", "comment", "template", "block", "abort"];
+
+/// This helper function parses an editor command (which can contain arguments, for example,
+/// `code --wait` or `emacsclient -t`) into the program and its arguments, using shell-style
+/// quoting rules.
+fn parse_editor(command: &str) -> Result<(String, Vec<String>), Error> {
+    let mut words = shlex::split(command)
+        .ok_or_else(|| Error::config(format!("Invalid editor command: {}", command)))?
+        .into_iter();
+
+    match words.next() {
+        Some(program) => Ok((program, words.collect())),
+        None => Err(Error::config(format!("Invalid editor command: {}", command))),
     }
 }
 
-async fn get_comment_from_editor() -> Result<Option<String>, String> {
-    let editor = detect_editor();
+/// This helper function determines the editor command from the `VISUAL` and `EDITOR` environment
+/// variables (in this order), and falls back to `nano` if neither is set.
+fn detect_editor() -> Result<(String, Vec<String>), Error> {
+    let command = [env::var("VISUAL"), env::var("EDITOR")]
+        .into_iter()
+        .flatten()
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+
+    parse_editor(&command)
+}
+
+/// This helper function opens the given text in an external editor and returns the edited text.
+///
+/// The text is edited in the draft comment for the update, which is kept until feedback was
+/// submitted successfully.
+async fn edit_in_editor(alias: &str, initial: &str) -> Result<String, Error> {
+    let (program, args) = detect_editor()?;
+    let path = prepare_draft(alias).await?;
 
-    let temp_file = tempfile::Builder::new()
-        .suffix(".md")
-        .tempfile()
-        .map_err(|err| err.to_string())?;
+    write(&path, initial)
+        .await
+        .map_err(|err| Error::io(format!("Failed to write the comment to {}", path.display()), err))?;
 
-    let mut cmd = Command::new(editor);
-    cmd.arg(temp_file.path());
-    cmd.status().await.map_err(|err| err.to_string())?;
+    let status = Command::new(&program)
+        .args(&args)
+        .arg(&path)
+        .status()
+        .await
+        .map_err(|err| Error::io(format!("Failed to run editor ({})", program), err))?;
+
+    if !status.success() {
+        println!("The draft comment was kept in {}.", path.display());
+        return Err(Error::io(
+            format!("Editor ({}) exited with an error", program),
+            std::io::Error::other(status.to_string()),
+        ));
+    }
 
-    let output = tokio::fs::read_to_string(temp_file)
+    read_to_string(&path)
         .await
-        .map_err(|err| err.to_string())?;
+        .map_err(|err| Error::io(format!("Failed to read the comment from {}", path.display()), err))
+}
+
+/// This helper function reads the comment and karma for an update, either with the inline editor,
+/// or by opening a pre-filled buffer in an external editor and parsing the feedback from the
+/// edited buffer.
+///
+/// If the inline editor is used or the feedback fields were removed from the buffer, the karma
+/// value is prompted for instead (unless it was already specified).
+async fn get_feedback(
+    prompter: &mut Prompter,
+    update: &Update,
+    binaries: &[&str],
+    comment: &str,
+    karma: Option<Karma>,
+) -> Result<EditedFeedback, Error> {
+    let mut feedback = match &mut prompter.inline {
+        Some(editor) => EditedFeedback {
+            comment: editor.read_comment(comment)?,
+            ..Default::default()
+        },
+        None => {
+            let mut buffer = format_buffer(update, binaries, comment, karma);
+
+            // a draft from a previous session would replace the pre-filled buffer (and template text)
+            if let Some(draft) = get_draft(&update.alias).await {
+                println!("There is a draft comment for this update from a previous session.");
+                if prompter
+                    .input("[r] restore the draft (default) / [d] discard it", &["r", "d"])?
+                    .to_lowercase()
+                    != "d"
+                {
+                    buffer = draft;
+                }
+            }
+
+            let buffer = edit_in_editor(&update.alias, &buffer).await?;
+            let feedback = parse_buffer(&buffer);
+
+            // empty drafts do not need to be kept
+            if feedback.comment.is_none() {
+                remove_draft(&update.alias).await;
+            }
+
+            feedback
+        },
+    };
 
-    match output.trim() {
-        "" => Ok(None),
-        _ => Ok(Some(output)),
+    for line in &feedback.invalid {
+        println!("Ignoring invalid feedback line: {}", line);
     }
+
+    if !feedback.has_fields {
+        feedback.karma = match karma {
+            Some(karma) => Some(karma),
+            None => str_to_karma(prompter.input("Karma (+1, 0, -1)", KARMA_VALUES)?.as_str()),
+        };
+    }
+
+    Ok(feedback)
 }
 
 /// This struct represents the user's progress through the list of installed updates.
@@ -47,8 +144,8 @@ async fn get_comment_from_editor() -> Result<Option<String>, String> {
 pub struct Progress {
     update_number: usize,
     total_updates: usize,
-    prev_commented: bool,
-    karma_reset: bool,
+    status: FeedbackStatus,
+    changes: Option<UpdateChanges>,
     prev_ignored: bool,
 }
 
@@ -56,15 +153,15 @@ impl Progress {
     pub fn new(
         update_number: usize,
         total_updates: usize,
-        prev_commented: bool,
-        karma_reset: bool,
+        status: FeedbackStatus,
+        changes: Option<UpdateChanges>,
         prev_ignored: bool,
     ) -> Progress {
         Progress {
             update_number,
             total_updates,
-            prev_commented,
-            karma_reset,
+            status,
+            changes,
             prev_ignored,
         }
     }
@@ -94,47 +191,113 @@ pub enum Feedback<'a> {
 }
 
 /// This helper function prints a prompt and reads a string from standard input.
-pub fn get_input(prompt: &str) -> String {
+///
+/// An error is returned if standard input has been closed (for example, when it is not connected
+/// to a terminal), instead of silently returning empty values for every prompt.
+pub fn get_input(prompt: &str) -> Result<String, Error> {
     let mut value = String::new();
 
     print!("{}: ", prompt);
-    stdout().flush().expect("Failed to print prompt to stdout.");
+    stdout()
+        .flush()
+        .map_err(|error| Error::io("Failed to print prompt to stdout", error))?;
 
-    stdin().read_line(&mut value).expect("Failed to read from stdin.");
+    let read = stdin()
+        .read_line(&mut value)
+        .map_err(|error| Error::io("Failed to read from stdin", error))?;
 
-    value.trim().to_string()
+    if read == 0 {
+        println!();
+        return Err(Error::io(
+            "Failed to read from stdin",
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "standard input was closed"),
+        ));
+    }
+
+    Ok(value.trim().to_string())
 }
 
 /// This helper parses a string into an optional karma value instead of an error.
 pub fn str_to_karma(string: &str) -> Option<Karma> {
-    match string.parse() {
-        Ok(karma) => Some(karma),
-        Err(_) => None,
+    string.parse().ok()
+}
+
+/// This struct reads answers to prompts, either from standard input, or with the inline editor
+/// (which supports line editing, a persistent prompt history, and tab completion).
+pub struct Prompter {
+    inline: Option<InlineEditor>,
+}
+
+impl Prompter {
+    /// This method constructs a new prompter. Comments are written with the inline editor instead
+    /// of an external editor if `inline` is `true`.
+    pub fn new(inline: bool) -> Result<Prompter, Error> {
+        let inline = if inline { Some(InlineEditor::new()?) } else { None };
+        Ok(Prompter { inline })
+    }
+
+    /// This method prints a prompt and reads the answer. With the inline editor, the given values
+    /// can be completed with the tab key.
+    pub fn input(&mut self, prompt: &str, completions: &[&str]) -> Result<String, Error> {
+        match &mut self.inline {
+            Some(editor) => editor.read_line(prompt, completions),
+            None => get_input(prompt),
+        }
     }
 }
 
+/// This helper function prints the available comment templates and prompts for one of them, either
+/// by number or by name.
+fn select_template<'t>(
+    prompter: &mut Prompter,
+    templates: &'t [RenderedTemplate],
+) -> Result<Option<&'t RenderedTemplate>, Error> {
+    println!();
+    println!("Templates:");
+    for (index, template) in templates.iter().enumerate() {
+        let first_line = template.text.lines().next().unwrap_or_default();
+        println!("[{}] {}: {}", index + 1, template.name, first_line);
+    }
+
+    let names: Vec<&str> = templates.iter().map(|template| template.name.as_str()).collect();
+    let input = prompter.input("Template", &names)?;
+
+    let template = match input.parse::<usize>() {
+        Ok(number) => number.checked_sub(1).and_then(|index| templates.get(index)),
+        Err(_) => templates.iter().find(|template| template.name == input),
+    };
+
+    Ok(template)
+}
+
 /// This helper function prompts for all feedback values for a given update.
 ///
 /// This includes:
 ///  - a prompt whether to skip the current update,
-///  - text feedback (can be multiple lines, leading and trailing whitespace will be stripped
-///    automatically; two empty lines or EOF (`Ctrl-D`) ends comment input)
+///  - text feedback, karma, and bug and test case feedback, which are entered together in a
+///    pre-filled buffer in an external editor
 ///
-/// If enabled at compile time, it also asks for bug and testcase feedback.
+/// If the feedback fields are removed from the editor buffer, karma and bug and test case feedback
+/// are prompted for separately. With the inline editor, comments are entered directly in the
+/// terminal instead (can be multiple lines, leading and trailing whitespace will be stripped
+/// automatically; two empty lines or EOF (`Ctrl-D`) ends comment input), and all other values
+/// are prompted for separately.
 pub async fn ask_feedback<'a>(
+    prompter: &mut Prompter,
     update: &'a Update,
+    instance: &BodhiInstance,
     progress: Progress,
-    builds: &[&str],
-    summaries: &HashMap<String, String>,
-    install_times: &HashMap<String, DateTime<Utc>>,
-) -> Result<Feedback<'a>, String> {
-    print_update(update, builds, summaries, install_times);
+    details: &UpdateDetails<'_>,
+    templates: &[RenderedTemplate],
+) -> Result<Feedback<'a>, Error> {
+    print_update(update, instance, details);
 
     enum Action {
         Skip,
         Ignore,
         Abort,
         Comment,
+        Template,
         Block,
     }
 
@@ -144,14 +307,27 @@ pub async fn ask_feedback<'a>(
         progress.total_updates - progress.update_number - 1
     );
 
-    if progress.prev_commented {
-        if progress.karma_reset {
-            println!("A comment for this update has already been submitted.");
-            println!("However, the update has since been edited, and karma has been reset.");
-        } else {
+    match progress.status {
+        FeedbackStatus::NeverCommented => {},
+        FeedbackStatus::CommentedWithoutKarma => {
+            println!("A comment for this update has already been submitted, but without karma.");
+        },
+        FeedbackStatus::KarmaForCurrentBuilds => {
             println!("A comment for this update has already been submitted.");
             println!("Any feedback / karma that is provided now will overwrite previous values.");
-        }
+        },
+        FeedbackStatus::KarmaForSupersededBuilds => {
+            println!("A comment for this update has already been submitted.");
+            println!("However, builds have since been added or removed, and karma has been reset.");
+        },
+        FeedbackStatus::KarmaMaybeReset => {
+            println!("A comment for this update has already been submitted.");
+            println!("However, the update has since been edited, and karma might have been reset.");
+        },
+    }
+
+    if let Some(changes) = progress.changes.as_ref().filter(|changes| !changes.is_empty()) {
+        print_changes(update, changes);
     }
 
     if progress.prev_ignored {
@@ -160,16 +336,24 @@ pub async fn ask_feedback<'a>(
 
     println!("Actions: [s] skip this update (default)");
     println!("       / [i] ignore this update permanently");
-    println!("       / [c] comment with feedback (opens an external editor)");
+    if prompter.inline.is_some() {
+        println!("       / [c] comment with feedback (in the terminal)");
+    } else {
+        println!("       / [c] comment with feedback (opens an external editor)");
+    }
+    if !templates.is_empty() {
+        println!("       / [t] comment with feedback from a template");
+    }
     println!("       / [b] block (ignore all packages from this update permanently)");
     println!("       / [a] abort (exit program)");
 
-    let action = match get_input("Action").to_lowercase().as_str() {
-        "s" => Action::Skip,
-        "i" => Action::Ignore,
-        "c" => Action::Comment,
-        "a" => Action::Abort,
-        "b" => Action::Block,
+    let action = match prompter.input("Action", ACTIONS)?.to_lowercase().as_str() {
+        "s" | "skip" => Action::Skip,
+        "i" | "ignore" => Action::Ignore,
+        "c" | "comment" => Action::Comment,
+        "t" | "template" if !templates.is_empty() => Action::Template,
+        "a" | "abort" => Action::Abort,
+        "b" | "block" => Action::Block,
         _ => Action::Skip,
     };
 
@@ -189,47 +373,134 @@ pub async fn ask_feedback<'a>(
         return Ok(Feedback::Abort);
     }
 
-    let comment = get_comment_from_editor().await?;
-    let karma = str_to_karma(get_input("Karma (+1, 0, -1)").as_str());
+    let edited = match action {
+        Action::Template => {
+            let Some(template) = select_template(prompter, templates)? else {
+                println!("Unknown template, skipping this update.");
+                return Ok(Feedback::Skip);
+            };
+
+            println!();
+            println!("{}", template.text);
+            println!();
+
+            match prompter
+                .input("[s] submit this comment (default) / [e] edit it first", &["s", "e"])?
+                .to_lowercase()
+                .as_str()
+            {
+                "e" => get_feedback(prompter, update, details.binaries, &template.text, template.karma).await?,
+                _ => EditedFeedback {
+                    comment: Some(template.text.clone()),
+                    karma: match template.karma {
+                        Some(karma) => Some(karma),
+                        None => str_to_karma(prompter.input("Karma (+1, 0, -1)", KARMA_VALUES)?.as_str()),
+                    },
+                    ..Default::default()
+                },
+            }
+        },
+        _ => get_feedback(prompter, update, details.binaries, "", None).await?,
+    };
+
+    let (comment, karma) = (edited.comment, edited.karma);
 
-    if let (None, None) = (&comment, &karma) {
+    if comment.is_none() && karma.is_none() && edited.bug_feedback.is_empty() && edited.testcase_feedback.is_empty() {
         println!("Provided neither comment nor karma, skipping this update.");
         return Ok(Feedback::Skip);
     };
 
-    let karma = match karma {
+    let mut karma = match karma {
         Some(karma) => karma,
         None => Karma::Neutral,
     };
 
-    let mut bug_feedback: Vec<(u32, Karma)> = Vec::new();
-    for bug in &update.bugs {
-        let bug_title = match &bug.title {
-            Some(title) => title.as_str(),
-            None => "(None)",
-        };
+    // positive karma for builds that have not been exercised yet needs confirmation
+    if karma == Karma::Positive && !details.restart.is_empty() {
+        println!("This update has not been exercised by the running system yet (see above).");
+        if prompter
+            .input("Submit positive karma anyway? [y/N]", &["y", "n"])?
+            .to_lowercase()
+            != "y"
+        {
+            println!("Not submitting karma for this update.");
+            karma = Karma::Neutral;
+        }
+    }
 
-        println!();
-        println!("{}: {}", bug.bug_id, bug_title);
-        if let Some(input) = str_to_karma(get_input("Bug Feedback (+1, 0, -1)").as_str()) {
-            bug_feedback.push((bug.bug_id, input));
-        } else {
-            println!("Skipped bug: {}", bug.bug_id);
-        };
+    // positive karma for recently installed updates is refused or needs confirmation
+    if let (Karma::Positive, Some(soak)) = (karma, details.soak) {
+        println!(
+            "This update has not been installed for {} yet (see above).",
+            proper_plural(soak.required_days.into(), "day")
+        );
+        if soak.strict
+            || prompter
+                .input("Submit positive karma anyway? [y/N]", &["y", "n"])?
+                .to_lowercase()
+                != "y"
+        {
+            println!("Not submitting karma for this update.");
+            karma = Karma::Neutral;
+        }
     }
 
+    let mut bug_feedback: Vec<(u32, Karma)> = Vec::new();
     let mut testcase_feedback: Vec<(&str, Karma)> = Vec::new();
-    if let Some(test_cases) = &update.test_cases {
-        for test_case in test_cases {
-            println!();
-            println!("{}", &test_case.name);
 
-            if let Some(input) = str_to_karma(get_input("Test Case Feedback (+1, 0, -1)").as_str()) {
-                testcase_feedback.push((&test_case.name, input));
+    if edited.has_fields {
+        // feedback was already provided in the editor buffer
+        for (bug_id, value) in edited.bug_feedback {
+            if update.bugs.iter().any(|bug| bug.bug_id == bug_id) {
+                bug_feedback.push((bug_id, value));
+            } else {
+                println!("Ignoring feedback for unknown bug: {}", bug_id);
+            }
+        }
+
+        for (name, value) in edited.testcase_feedback {
+            let test_case = update
+                .test_cases
+                .iter()
+                .flatten()
+                .find(|test_case| test_case.name == name);
+
+            if let Some(test_case) = test_case {
+                testcase_feedback.push((&test_case.name, value));
             } else {
-                println!("Skipped test case: {}", &test_case.name);
+                println!("Ignoring feedback for unknown test case: {}", name);
+            }
+        }
+    } else {
+        for bug in &update.bugs {
+            let bug_title = match &bug.title {
+                Some(title) => title.as_str(),
+                None => "(None)",
+            };
+
+            println!();
+            println!("{}: {}", bug.bug_id, bug_title);
+            if let Some(input) = str_to_karma(prompter.input("Bug Feedback (+1, 0, -1)", KARMA_VALUES)?.as_str()) {
+                bug_feedback.push((bug.bug_id, input));
+            } else {
+                println!("Skipped bug: {}", bug.bug_id);
             };
         }
+
+        if let Some(test_cases) = &update.test_cases {
+            for test_case in test_cases {
+                println!();
+                println!("{}", &test_case.name);
+
+                if let Some(input) =
+                    str_to_karma(prompter.input("Test Case Feedback (+1, 0, -1)", KARMA_VALUES)?.as_str())
+                {
+                    testcase_feedback.push((&test_case.name, input));
+                } else {
+                    println!("Skipped test case: {}", &test_case.name);
+                };
+            }
+        }
     }
 
     println!();
@@ -245,3 +516,27 @@ pub async fn ask_feedback<'a>(
         })
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_commands() {
+        assert_eq!(parse_editor("vim").unwrap(), (String::from("vim"), vec![]));
+        assert_eq!(
+            parse_editor("code --wait").unwrap(),
+            (String::from("code"), vec![String::from("--wait")])
+        );
+        assert_eq!(
+            parse_editor("'/opt/My Editor/bin/editor' -t \"new window\"").unwrap(),
+            (
+                String::from("/opt/My Editor/bin/editor"),
+                vec![String::from("-t"), String::from("new window")]
+            )
+        );
+        assert!(parse_editor("emacsclient 'unterminated").is_err());
+        assert!(parse_editor("").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use bodhi::BodhiClientBuilder;
+use url::Url;
+
+use crate::config::FedoraConfig;
+use crate::error::Error;
+
+const PRODUCTION_URL: &str = "https://bodhi.fedoraproject.org";
+const STAGING_URL: &str = "https://bodhi.stg.fedoraproject.org";
+
+// OpenID endpoint that is used for custom bodhi instances if none was specified
+const DEFAULT_OPENID_URL: &str = "https://id.fedoraproject.org/api/v1/";
+
+/// This enum represents the bodhi instance that updates are queried from and feedback is submitted
+/// to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BodhiInstance {
+    /// Fedora production instance of bodhi (default)
+    Production,
+    /// Fedora staging instance of bodhi
+    Staging,
+    /// custom bodhi instance with matching OpenID endpoint
+    Custom {
+        /// base URL of the bodhi instance
+        url: String,
+        /// URL of the OpenID endpoint that is used for authentication
+        openid_url: String,
+    },
+}
+
+impl BodhiInstance {
+    /// This method parses the bodhi instance from a value for `--bodhi-url` or the `bodhi-url`
+    /// configuration key, which can be either "production", "staging", or a URL.
+    pub fn new(url: &str, openid_url: Option<&str>) -> Result<BodhiInstance, Error> {
+        let instance = match url {
+            "production" => BodhiInstance::Production,
+            "staging" => BodhiInstance::Staging,
+            url => {
+                Url::parse(url).map_err(|error| Error::config_with(format!("Invalid bodhi URL ({})", url), error))?;
+
+                let openid_url = openid_url.unwrap_or(DEFAULT_OPENID_URL);
+                Url::parse(openid_url)
+                    .map_err(|error| Error::config_with(format!("Invalid OpenID URL ({})", openid_url), error))?;
+
+                BodhiInstance::Custom {
+                    url: url.trim_end_matches('/').to_string(),
+                    openid_url: openid_url.to_string(),
+                }
+            },
+        };
+
+        Ok(instance)
+    }
+
+    /// This method determines the bodhi instance from CLI arguments (which take precedence) and
+    /// settings from the configuration file.
+    pub fn from_args(
+        url: Option<&str>,
+        openid_url: Option<&str>,
+        config: Option<&FedoraConfig>,
+    ) -> Result<BodhiInstance, Error> {
+        let fuf = config.and_then(|config| config.fuf.as_ref());
+
+        let (url, openid_url) = match url {
+            Some(url) => (url, openid_url),
+            None => match fuf.and_then(|fuf| fuf.bodhi_url.as_deref()) {
+                Some(url) => (url, openid_url.or(fuf.and_then(|fuf| fuf.openid_url.as_deref()))),
+                None => return Ok(BodhiInstance::Production),
+            },
+        };
+
+        BodhiInstance::new(url, openid_url)
+    }
+
+    /// This method returns the base URL of the bodhi instance.
+    pub fn url(&self) -> &str {
+        match self {
+            BodhiInstance::Production => PRODUCTION_URL,
+            BodhiInstance::Staging => STAGING_URL,
+            BodhiInstance::Custom { url, .. } => url,
+        }
+    }
+
+    /// This method returns the URL of the web page for an update.
+    pub fn update_url(&self, alias: &str) -> String {
+        format!("{}/updates/{}", self.url(), alias)
+    }
+
+    /// This method returns a [`BodhiClientBuilder`] that targets the bodhi instance.
+    pub fn builder<'a>(&self) -> BodhiClientBuilder<'a> {
+        match self {
+            BodhiInstance::Production => BodhiClientBuilder::default(),
+            BodhiInstance::Staging => BodhiClientBuilder::staging(),
+            BodhiInstance::Custom { url, openid_url } => BodhiClientBuilder::custom(url.clone(), openid_url.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            BodhiInstance::new("production", None).unwrap(),
+            BodhiInstance::Production
+        );
+        assert_eq!(BodhiInstance::new("staging", None).unwrap(), BodhiInstance::Staging);
+
+        let custom = BodhiInstance::new("http://localhost:8080/", None).unwrap();
+        assert_eq!(
+            custom,
+            BodhiInstance::Custom {
+                url: String::from("http://localhost:8080"),
+                openid_url: String::from(DEFAULT_OPENID_URL),
+            }
+        );
+        assert_eq!(
+            custom.update_url("FEDORA-2024-0001aaaa"),
+            "http://localhost:8080/updates/FEDORA-2024-0001aaaa"
+        );
+
+        assert!(BodhiInstance::new("bodhi", None).is_err());
+        assert!(BodhiInstance::new("http://localhost:8080", Some("openid")).is_err());
+    }
+
+    #[test]
+    fn precedence() {
+        let config: FedoraConfig = toml::from_str(
+            r#"
+            [FAS]
+            username = "tester"
+
+            [fedora-update-feedback]
+            bodhi-url = "http://localhost:8080"
+            openid-url = "http://localhost:8081/api/v1/"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            BodhiInstance::from_args(None, None, None).unwrap(),
+            BodhiInstance::Production
+        );
+        assert_eq!(
+            BodhiInstance::from_args(None, None, Some(&config)).unwrap(),
+            BodhiInstance::Custom {
+                url: String::from("http://localhost:8080"),
+                openid_url: String::from("http://localhost:8081/api/v1/"),
+            }
+        );
+        assert_eq!(
+            BodhiInstance::from_args(Some("staging"), None, Some(&config)).unwrap(),
+            BodhiInstance::Staging
+        );
+    }
+}
@@ -0,0 +1,64 @@
+#![warn(missing_docs)]
+#![warn(clippy::unwrap_used)]
+
+//! This crate contains the `fedora-update-feedback` binary and some helper functionality. If
+//! something turns out to be generally useful, it can be upstreamed into either the
+//! [`fedora`][fedora] or [`bodhi`][bodhi] crates.
+//!
+//! The [`app::App`] type exposes the individual phases of a `fedora-update-feedback` run, so they
+//! can be reused by other tools. The [`nvr`], [`parse`], [`filter`], and [`sysinfo`] modules
+//! contain the types and functions that are used for matching installed packages with updates.
+//!
+//! [fedora]: https://crates.io/crates/fedora
+//! [bodhi]: https://crates.io/crates/bodhi
+
+/// phases of a `fedora-update-feedback` run
+pub mod app;
+/// feedback for multiple updates that is read from a file
+pub mod batch;
+/// excerpts from the `%changelog` of installed packages
+pub mod changelog;
+/// command line arguments
+pub mod cli;
+/// configuration file and legacy username file
+pub mod config;
+/// draft comments that are kept until feedback was submitted
+pub mod drafts;
+/// error type with exit codes and hints
+pub mod error;
+/// matching of installed packages with updates
+pub mod filter;
+/// system information that is appended to comments
+pub mod footer;
+/// previous feedback and edit history of updates
+pub mod history;
+/// lists of ignored updates and packages
+pub mod ignore;
+/// bodhi instance selection
+pub mod instance;
+/// machine-readable listing of installed updates waiting for feedback
+pub mod list;
+/// name-version-release triples of source packages
+pub mod nvr;
+/// parsers for NEVRA strings, RPM file names, and NVR strings
+pub mod parse;
+/// local queue for feedback that has not been submitted yet
+pub mod queue;
+/// checks whether installed updates are in use by the running system
+pub mod restart;
+/// minimum time between installing updates and providing positive karma
+pub mod soak;
+/// summary of a review session
+pub mod summary;
+/// information about the current system and installed packages
+pub mod sysinfo;
+/// comment templates with placeholders
+pub mod template;
+
+mod checks;
+mod editor;
+mod inline;
+mod input;
+mod output;
+mod query;
+mod secrets;
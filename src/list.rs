@@ -0,0 +1,228 @@
+use bodhi::Update;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::filter::{install_time, missing_builds};
+use crate::history::{feedback_status, FeedbackStatus};
+use crate::instance::BodhiInstance;
+use crate::sysinfo::InstalledPackages;
+
+/// This struct contains information about an installed update that is waiting for feedback, in a
+/// format that is suitable for printing a table or serializing to JSON.
+#[derive(Debug, Serialize)]
+pub struct ListEntry {
+    /// update alias
+    pub alias: String,
+    /// human-readable update title
+    pub title: String,
+    /// update type (for example, "bugfix" or "enhancement")
+    #[serde(rename = "type")]
+    pub update_type: String,
+    /// URL of the web page for the update
+    pub url: String,
+    /// current karma of the update
+    pub karma: Option<i32>,
+    /// karma threshold for pushing the update to stable
+    pub stable_karma: Option<i32>,
+    /// karma threshold for unpushing the update
+    pub unstable_karma: Option<i32>,
+    /// builds from this update that are installed
+    pub builds: Vec<String>,
+    /// builds from this update that are not installed
+    pub missing_builds: Vec<String>,
+    /// time when the most recently installed package from this update was installed
+    pub installed: Option<DateTime<Utc>>,
+    /// feedback that the current user has previously provided for the update
+    pub feedback: FeedbackStatus,
+    /// whether the update is in the list of ignored updates
+    pub ignored: bool,
+}
+
+impl ListEntry {
+    /// This method collects information about an installed update.
+    pub fn new(
+        update: &Update,
+        username: &str,
+        instance: &BodhiInstance,
+        builds: &[String],
+        installed: &InstalledPackages,
+        ignored: bool,
+    ) -> ListEntry {
+        ListEntry {
+            alias: update.alias.clone(),
+            title: update.title.clone(),
+            update_type: update.update_type.to_string(),
+            url: instance.update_url(&update.alias),
+            karma: update.karma,
+            stable_karma: update.stable_karma,
+            unstable_karma: update.unstable_karma,
+            builds: builds.to_vec(),
+            missing_builds: missing_builds(update, &installed.packages)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|build| build.nvr)
+                .collect(),
+            installed: install_time(builds, installed),
+            feedback: feedback_status(update, username),
+            ignored,
+        }
+    }
+
+    /// This method returns the current karma and the karma thresholds in a compact format.
+    fn karma(&self) -> String {
+        let format = |karma: Option<i32>| match karma {
+            Some(karma) if karma > 0 => format!("+{}", karma),
+            Some(karma) => karma.to_string(),
+            None => String::from("?"),
+        };
+
+        format!(
+            "{} ({}/{})",
+            format(self.karma),
+            format(self.stable_karma),
+            format(self.unstable_karma)
+        )
+    }
+
+    /// This method returns the previous feedback and ignored state in a compact format.
+    fn state(&self) -> String {
+        let mut states = Vec::new();
+
+        match self.feedback {
+            FeedbackStatus::NeverCommented => {},
+            FeedbackStatus::CommentedWithoutKarma => states.push("commented"),
+            FeedbackStatus::KarmaForCurrentBuilds => states.push("karma given"),
+            FeedbackStatus::KarmaForSupersededBuilds => states.push("karma reset"),
+            FeedbackStatus::KarmaMaybeReset => states.push("karma maybe reset"),
+        }
+        if !self.missing_builds.is_empty() {
+            states.push("partial");
+        }
+        if self.ignored {
+            states.push("ignored");
+        }
+
+        if states.is_empty() {
+            String::from("-")
+        } else {
+            states.join(", ")
+        }
+    }
+}
+
+/// This helper function returns a compact representation of the time that has passed since a
+/// package was installed.
+fn age(datetime: Option<DateTime<Utc>>, now: DateTime<Utc>) -> String {
+    let datetime = match datetime {
+        Some(datetime) => datetime,
+        None => return String::from("?"),
+    };
+
+    let duration = (now - datetime).max(Duration::zero());
+
+    if duration >= Duration::days(1) {
+        format!("{}d", duration.num_days())
+    } else if duration >= Duration::hours(1) {
+        format!("{}h", duration.num_hours())
+    } else {
+        format!("{}m", duration.num_minutes())
+    }
+}
+
+/// This helper function renders a list of installed updates as a table with aligned columns.
+pub fn format_table(entries: &[ListEntry], now: DateTime<Utc>) -> String {
+    let header = ["ALIAS", "TYPE", "KARMA", "INSTALLED", "STATE", "TITLE", "BUILDS"];
+
+    let mut rows: Vec<[String; 7]> = vec![header.map(String::from)];
+    for entry in entries {
+        rows.push([
+            entry.alias.clone(),
+            entry.update_type.clone(),
+            entry.karma(),
+            age(entry.installed, now),
+            entry.state(),
+            entry.title.clone(),
+            entry.builds.join(" "),
+        ]);
+    }
+
+    let mut widths = [0usize; 7];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        lines.push(cells.join("  ").trim_end().to_string());
+    }
+
+    lines.join("\n")
+}
+
+/// This helper function serializes a list of installed updates as JSON.
+pub fn format_json(entries: &[ListEntry]) -> Result<String, Error> {
+    serde_json::to_string_pretty(entries).map_err(|error| Error::parse_with("Failed to serialize updates", error))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn entry() -> ListEntry {
+        ListEntry {
+            alias: String::from("FEDORA-2024-0001aaaa"),
+            title: String::from("foo-1.2-1.fc40"),
+            update_type: String::from("bugfix"),
+            url: String::from("https://bodhi.fedoraproject.org/updates/FEDORA-2024-0001aaaa"),
+            karma: Some(1),
+            stable_karma: Some(3),
+            unstable_karma: Some(-3),
+            builds: vec![String::from("foo-1.2-1.fc40")],
+            missing_builds: Vec::new(),
+            installed: Some(DateTime::from_timestamp(1714644000, 0).unwrap()),
+            feedback: FeedbackStatus::KarmaForSupersededBuilds,
+            ignored: false,
+        }
+    }
+
+    #[test]
+    fn table() {
+        let now = DateTime::from_timestamp(1714644000 + 3 * 86400 + 3600, 0).unwrap();
+
+        assert_eq!(
+            format_table(&[entry()], now),
+            "ALIAS                 TYPE    KARMA       INSTALLED  STATE        TITLE           BUILDS\n\
+             FEDORA-2024-0001aaaa  bugfix  +1 (+3/-3)  3d         karma reset  foo-1.2-1.fc40  foo-1.2-1.fc40"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let value: serde_json::Value = serde_json::from_str(&format_json(&[entry()]).unwrap()).unwrap();
+
+        assert_eq!(value[0]["type"], "bugfix");
+        assert_eq!(value[0]["stable_karma"], 3);
+        assert_eq!(value[0]["installed"], "2024-05-02T10:00:00Z");
+        assert_eq!(value[0]["builds"][0], "foo-1.2-1.fc40");
+        assert_eq!(value[0]["feedback"], "karma_for_superseded_builds");
+        assert_eq!(value[0]["missing_builds"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn partial() {
+        let mut entry = entry();
+        entry.feedback = FeedbackStatus::NeverCommented;
+        entry.missing_builds = vec![String::from("foo-libs-1.2-1.fc40")];
+
+        assert_eq!(entry.state(), "partial");
+    }
+}
@@ -3,10 +3,11 @@ use std::convert::TryFrom;
 use std::fmt::Display;
 use std::str::FromStr;
 
+use crate::error::Error;
 use crate::parse::parse_nvr;
 
 /// This struct encapsulates a parsed NVR string.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct NVR {
     /// name of the package
@@ -24,7 +25,7 @@ impl Display for NVR {
 }
 
 impl TryFrom<&str> for NVR {
-    type Error = String;
+    type Error = Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         let (n, v, r) = parse_nvr(value)?;
@@ -37,7 +38,7 @@ impl TryFrom<&str> for NVR {
 }
 
 impl FromStr for NVR {
-    type Err = String;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         TryFrom::try_from(s)
@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::{stdout, Write};
 
@@ -5,34 +6,101 @@ use bodhi::{Comment, Update};
 use chrono::{DateTime, Duration, Utc};
 use terminal_size::{terminal_size, Width};
 
+use crate::changelog::ChangelogExcerpt;
+use crate::filter::MissingBuild;
+use crate::history::UpdateChanges;
+use crate::instance::BodhiInstance;
 use crate::parse::parse_nvr;
+use crate::restart::RestartReason;
+use crate::soak::SoakTime;
+use crate::sysinfo::InstalledPackages;
 
-/// This function draws a pretty progress bar with this format:
+/// This helper function formats a pretty progress bar with this format:
 ///
 /// ` Prefix: [ =========                     ] 22% `
-///
-/// It's necessary to print a newline character after the progress bar reaches 100%.
-pub fn progress_bar(prefix: &str, p: u32, ps: u32) {
-    let columns: u32 = match terminal_size() {
-        Some((Width(width), _)) => width as u32,
-        None => return,
-    };
-
-    let width: u32 = columns - (prefix.len() as u32) - 13;
+fn format_progress_bar(prefix: &str, p: u32, ps: u32, columns: u32) -> String {
+    let width: u32 = columns.saturating_sub(prefix.len() as u32 + 13);
 
-    let progress = ((p as f64) / (ps as f64) * (width as f64)) as u32;
+    let fraction = if ps == 0 { 1f64 } else { (p as f64) / (ps as f64) };
+    let progress = ((fraction * (width as f64)) as u32).min(width);
     let remaining = width - progress;
 
-    let line = format!(
+    format!(
         " {}: [ {}{} ] {:>3}% ",
         prefix,
         "=".repeat(progress as usize),
         " ".repeat(remaining as usize),
-        ((p as f64) / (ps as f64) * 100f64) as u32,
-    );
+        (fraction * 100f64) as u32,
+    )
+}
+
+/// This helper function formats the progress of one or more queries as a single line. A single
+/// query is shown as a progress bar, and multiple queries are shown as a list of percentages.
+fn format_progress(entries: &[(String, u32, u32)], columns: u32) -> String {
+    if let [(prefix, p, ps)] = entries {
+        return format_progress_bar(prefix, *p, *ps, columns);
+    }
+
+    let line = entries
+        .iter()
+        .map(|(prefix, p, ps)| {
+            let percent = if *ps == 0 { 100 } else { p * 100 / ps };
+            format!("{}: {:>3}%", prefix, percent)
+        })
+        .collect::<Vec<String>>()
+        .join(" | ");
+
+    // pad the line to overwrite longer lines that were printed before
+    format!(" {:<width$}", line, width = columns.saturating_sub(2) as usize)
+}
+
+/// This struct displays the progress of one or more bodhi queries (which can run concurrently) on
+/// a single line.
+///
+/// It's necessary to print a newline character after all queries have finished.
+#[derive(Debug, Default)]
+pub struct QueryProgress {
+    hidden: bool,
+    entries: RefCell<Vec<(String, u32, u32)>>,
+}
+
+impl QueryProgress {
+    /// This method constructs a new progress display.
+    pub fn new() -> QueryProgress {
+        QueryProgress::default()
+    }
+
+    /// This method constructs a progress display that does not print anything (for example, for
+    /// queries that run in the background).
+    pub fn hidden() -> QueryProgress {
+        QueryProgress {
+            hidden: true,
+            ..Default::default()
+        }
+    }
+
+    /// This method updates the progress of the query with the given prefix, and redraws the
+    /// progress display.
+    pub fn update(&self, prefix: &str, p: u32, ps: u32) {
+        let mut entries = self.entries.borrow_mut();
+
+        match entries.iter_mut().find(|(name, _, _)| name == prefix) {
+            Some(entry) => *entry = (prefix.to_string(), p, ps),
+            None => entries.push((prefix.to_string(), p, ps)),
+        }
+
+        if self.hidden {
+            return;
+        }
+
+        let columns: u32 = match terminal_size() {
+            Some((Width(width), _)) => width as u32,
+            None => return,
+        };
 
-    print!("\r{}", &line);
-    stdout().flush().expect("Failed to write to stdout.");
+        print!("\r{}", format_progress(&entries, columns));
+        stdout().flush().expect("Failed to write to stdout.");
+    }
 }
 
 /// This helper function returns the duration from a datetime that lies in the past until now.
@@ -77,13 +145,26 @@ fn pretty_duration(duration: Duration) -> String {
     }
 }
 
+/// This struct contains information about the locally installed builds of an update, which is
+/// printed together with the details of the update.
+#[derive(Debug)]
+pub struct UpdateDetails<'a> {
+    /// installed binary packages from the update
+    pub binaries: &'a [&'a str],
+    /// information about installed packages (summaries and installation times)
+    pub installed: &'a InstalledPackages,
+    /// new `%changelog` entries of installed builds
+    pub changelogs: &'a [(&'a str, &'a ChangelogExcerpt)],
+    /// builds from the update that are not installed
+    pub missing: &'a [MissingBuild],
+    /// reasons why the installed builds are not in use yet
+    pub restart: &'a [RestartReason],
+    /// minimum time before providing positive karma, if it has not passed yet
+    pub soak: Option<&'a SoakTime>,
+}
+
 /// This helper function pretty-prints an update.
-pub fn print_update(
-    update: &Update,
-    builds: &[&str],
-    summaries: &HashMap<String, String>,
-    install_times: &HashMap<String, DateTime<Utc>>,
-) {
+pub fn print_update(update: &Update, instance: &BodhiInstance, details: &UpdateDetails) {
     let karma = match update.karma {
         Some(karma) => karma.to_string(),
         None => "?".to_string(),
@@ -153,10 +234,7 @@ pub fn print_update(
     // block for rendering width-independent table
     println!();
 
-    println!(
-        "URL:            https://bodhi.fedoraproject.org/updates/{}",
-        &update.alias
-    );
+    println!("URL:            {}", instance.update_url(&update.alias));
     println!("Update type:    {}", update.update_type);
 
     // special-case some properties that are not always correctly set by bodhi servers
@@ -219,12 +297,12 @@ pub fn print_update(
     };
 
     println!("Locally installed packages contained in this update:");
-    for build in builds {
+    for build in details.binaries {
         let name = parse_nvr(build)
             .unwrap_or_else(|_| panic!("Failed to parse build NVR: {}", build))
             .0;
-        let summary = summaries.get(name);
-        let install_time = install_times.get(*build);
+        let summary = details.installed.summaries.get(name);
+        let install_time = details.installed.install_times.get(*build);
 
         println!("- {}", build);
 
@@ -237,6 +315,27 @@ pub fn print_update(
         }
     }
 
+    if !details.missing.is_empty() {
+        print_missing(update, details.missing);
+    }
+
+    if !details.restart.is_empty() {
+        print_restart(details.restart);
+    }
+
+    if let Some(soak) = details.soak {
+        println!();
+        println!(
+            "NOTE: This update was installed {} ago, but positive karma should only be provided after {}.",
+            pretty_duration(soak.installed_for),
+            proper_plural(soak.required_days.into(), "day")
+        );
+    }
+
+    for (build, changelog) in details.changelogs {
+        print_changelog(build, changelog);
+    }
+
     if let Some(comments) = &update.comments {
         let mut sorted: Vec<&Comment> = comments.iter().filter(|c| c.user.name != "bodhi").collect();
 
@@ -268,6 +367,138 @@ pub fn print_update(
     println!();
 }
 
+/// This helper function prints a warning for updates where only some builds are installed, and
+/// how the missing builds can be installed.
+fn print_missing(update: &Update, missing: &[MissingBuild]) {
+    println!();
+    println!(
+        "WARNING: Only {} of {} builds from this update are installed:",
+        update.builds.len() - missing.len(),
+        update.builds.len()
+    );
+
+    for build in missing {
+        match &build.installed {
+            Some(installed) => println!("- {} (installed version: {})", build.nvr, installed),
+            None => println!("- {} (not installed)", build.nvr),
+        }
+    }
+
+    println!("To install the missing builds for testing, run:");
+    if missing.iter().any(|build| build.installed.is_some()) {
+        println!(
+            "  sudo dnf upgrade --refresh --enablerepo=updates-testing --advisory={}",
+            update.alias
+        );
+    }
+    // builds from which no packages are installed cannot be pulled in by upgrading, so they are
+    // downloaded from koji into a separate directory and installed from there
+    for build in missing.iter().filter(|build| build.installed.is_none()) {
+        println!(
+            "  (mkdir {nvr} && cd {nvr} && koji download-build --arch=noarch --arch=$(uname -m) {nvr} && sudo dnf install ./*.rpm)",
+            nvr = build.nvr
+        );
+    }
+}
+
+/// This helper function prints a warning for installed builds that are not in use by the running
+/// system yet, since feedback for them would not be based on actual testing.
+fn print_restart(reasons: &[RestartReason]) {
+    println!();
+    println!("WARNING: This update has not been exercised by the running system yet:");
+
+    for reason in reasons {
+        match reason {
+            RestartReason::KernelNotBooted { build, running } => {
+                println!("- {} is installed, but kernel {} is running.", build, running);
+                println!("  Reboot into the new kernel before providing feedback.");
+            },
+            RestartReason::InstalledAfterBoot { build, boot_time } => {
+                println!(
+                    "- {} was installed after the system was booted ({}).",
+                    build,
+                    boot_time.format("%Y-%m-%d %H:%M UTC")
+                );
+                println!("  Reboot the system before providing feedback.");
+            },
+            RestartReason::ProcessesNotRestarted { build, processes } => {
+                println!(
+                    "- Running processes still use files from before {} was installed:",
+                    build
+                );
+                println!("  {}", processes.join(", "));
+                println!("  Restart them before providing feedback.");
+            },
+        }
+    }
+}
+
+/// This helper function prints new `%changelog` entries of an installed build.
+fn print_changelog(build: &str, changelog: &ChangelogExcerpt) {
+    if changelog.entries.is_empty() {
+        return;
+    }
+
+    println!();
+    match &changelog.since {
+        Some(since) => println!("Changelog of {} (new since {}):", build, since),
+        None => println!("Latest changelog entry of {}:", build),
+    }
+
+    for entry in &changelog.entries {
+        println!("* {}", entry.header);
+        for line in entry.text.lines() {
+            println!("  {}", line);
+        }
+    }
+
+    if changelog.truncated {
+        println!("  ... (older entries omitted)");
+    }
+}
+
+/// This helper function prints what has changed in an update since the user last commented on it.
+pub fn print_changes(update: &Update, changes: &UpdateChanges) {
+    println!("Changes since your last comment:");
+
+    for (old, new) in &changes.replaced_builds {
+        println!("- replaced build: {} -> {}", old, new);
+    }
+    for build in &changes.added_builds {
+        println!("- added build: {}", build);
+    }
+    for build in &changes.removed_builds {
+        println!("- removed build: {}", build);
+    }
+
+    for id in &changes.new_bugs {
+        match update.bugs.iter().find(|bug| bug.bug_id == *id) {
+            Some(bug) => match &bug.title {
+                Some(title) => println!("- new bug: {} ({})", bug.url(), title.trim()),
+                None => println!("- new bug: {}", bug.url()),
+            },
+            None => println!("- new bug: {}", id),
+        }
+    }
+    for name in &changes.new_test_cases {
+        println!("- new test case: {}", name);
+    }
+
+    if let Some(previous) = &changes.previous_notes {
+        println!("- update notes were changed:");
+
+        let old: Vec<&str> = previous.trim().lines().collect();
+        let new: Vec<&str> = update.notes.trim().lines().collect();
+
+        for line in old.iter().filter(|line| !new.contains(line)) {
+            println!("    - {}", line);
+        }
+        for line in new.iter().filter(|line| !old.contains(line)) {
+            println!("    + {}", line);
+        }
+    }
+}
+
 pub fn print_server_messages(caveats: &[HashMap<String, String>]) {
     if !caveats.is_empty() {
         println!("Server messages:");
@@ -279,3 +510,26 @@ pub fn print_server_messages(caveats: &[HashMap<String, String>]) {
         }
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress() {
+        let single = vec![(String::from("Updates (testing)"), 1, 4)];
+        assert_eq!(
+            format_progress(&single, 50),
+            " Updates (testing): [ =====                ]  25% "
+        );
+
+        let multiple = vec![
+            (String::from("Updates (testing)"), 1, 4),
+            (String::from("Updates (pending)"), 1, 1),
+        ];
+        let line = format_progress(&multiple, 60);
+        assert_eq!(line.len(), 59);
+        assert_eq!(line.trim_end(), " Updates (testing):  25% | Updates (pending): 100%");
+    }
+}
@@ -1,10 +1,12 @@
+use crate::error::Error;
+
 /// This helper function parses a NEVRA string into its components.
 #[allow(clippy::many_single_char_names)]
-pub fn parse_nevra(nevra: &str) -> Result<(&str, &str, &str, &str, &str), String> {
+pub fn parse_nevra(nevra: &str) -> Result<(&str, &str, &str, &str, &str), Error> {
     let mut nevr_a: Vec<&str> = nevra.rsplitn(2, '.').collect();
 
     if nevr_a.len() != 2 {
-        return Err(format!("Unexpected error when parsing NEVRAs: {}", nevra));
+        return Err(Error::parse(format!("Unexpected error when parsing NEVRAs: {}", nevra)));
     };
 
     // rsplitn returns things in reverse order
@@ -14,7 +16,7 @@ pub fn parse_nevra(nevra: &str) -> Result<(&str, &str, &str, &str, &str), String
     let mut n_ev_r: Vec<&str> = nevr.rsplitn(3, '-').collect();
 
     if n_ev_r.len() != 3 {
-        return Err(format!("Unexpected error when parsing NEVRAs: {}", nevr));
+        return Err(Error::parse(format!("Unexpected error when parsing NEVRAs: {}", nevr)));
     };
 
     // rsplitn returns things in reverse order
@@ -36,11 +38,14 @@ pub fn parse_nevra(nevra: &str) -> Result<(&str, &str, &str, &str, &str), String
 
 /// This helper function parses a NEVRA.rpm string into its components.
 #[allow(clippy::many_single_char_names)]
-pub fn parse_filename(nevrax: &str) -> Result<(&str, &str, &str, &str, &str), String> {
+pub fn parse_filename(nevrax: &str) -> Result<(&str, &str, &str, &str, &str), Error> {
     let mut nevra_x: Vec<&str> = nevrax.rsplitn(2, '.').collect();
 
     if nevra_x.len() != 2 {
-        return Err(format!("Unexpected error when parsing dnf output: {}", nevrax));
+        return Err(Error::parse(format!(
+            "Unexpected error when parsing RPM file names: {}",
+            nevrax
+        )));
     };
 
     // rsplitn returns things in reverse order
@@ -53,11 +58,11 @@ pub fn parse_filename(nevrax: &str) -> Result<(&str, &str, &str, &str, &str), St
 
 /// This helper function parses an NVR string into its components.
 #[allow(clippy::many_single_char_names)]
-pub fn parse_nvr(nvr: &str) -> Result<(&str, &str, &str), String> {
+pub fn parse_nvr(nvr: &str) -> Result<(&str, &str, &str), Error> {
     let mut n_v_r: Vec<&str> = nvr.rsplitn(3, '-').collect();
 
     if n_v_r.len() != 3 {
-        return Err(format!("Unexpected error when parsing NEVRAs: {}", nvr));
+        return Err(Error::parse(format!("Unexpected error when parsing NEVRAs: {}", nvr)));
     };
 
     // rsplitn returns things in reverse order
@@ -1,13 +1,18 @@
 use bodhi::{BodhiClient, ContentType, FedoraRelease, QueryError, Update, UpdateStatus};
 
-use crate::output::progress_bar;
-
-/// This helper function queries updates in "testing" state for a specific release, and prints a
-/// nice progress bar to indicate query progress.
-pub async fn query_testing(bodhi: &BodhiClient, release: FedoraRelease) -> Result<Vec<Update>, String> {
+use crate::error::Error;
+use crate::output::QueryProgress;
+
+/// This helper function queries updates in "testing" state for a specific release, and shows its
+/// progress in the given progress display.
+pub async fn query_testing(
+    bodhi: &BodhiClient,
+    release: FedoraRelease,
+    progress: &QueryProgress,
+) -> Result<Vec<Update>, Error> {
     let testing = "Updates (testing)";
 
-    let testing_progress = |p, ps| progress_bar(testing, p, ps);
+    let testing_progress = |p, ps| progress.update(testing, p, ps);
 
     let releases = vec![release];
     let testing_query = bodhi::query::UpdateQuery::new()
@@ -26,13 +31,13 @@ pub async fn query_testing(bodhi: &BodhiClient, release: FedoraRelease) -> Resul
                         messages.push(format!("{}: {}", key, value));
                     }
                 }
-                Err(format!(
+                Err(Error::bodhi(format!(
                     "Internal server error:\n{}\n{}\n",
                     error.status,
                     messages.join("\n")
-                ))
+                )))
             } else {
-                Err(error.to_string())
+                Err(Error::bodhi_with("Failed to query 'testing' updates", error))
             }
         },
     };
@@ -40,11 +45,15 @@ pub async fn query_testing(bodhi: &BodhiClient, release: FedoraRelease) -> Resul
     Ok(testing_updates)
 }
 
-/// This helper function queries updates in "obsolete" state for a specific release, and prints a
-/// nice progress bar to indicate query progress.
-pub async fn query_obsoleted(bodhi: &BodhiClient, release: FedoraRelease) -> Result<Vec<Update>, String> {
+/// This helper function queries updates in "obsolete" state for a specific release, and shows its
+/// progress in the given progress display.
+pub async fn query_obsoleted(
+    bodhi: &BodhiClient,
+    release: FedoraRelease,
+    progress: &QueryProgress,
+) -> Result<Vec<Update>, Error> {
     let obsolete = "Updates (obsolete)";
-    let obsolete_progress = |p, ps| progress_bar(obsolete, p, ps);
+    let obsolete_progress = |p, ps| progress.update(obsolete, p, ps);
 
     let releases = vec![release];
     let obsolete_query = bodhi::query::UpdateQuery::new()
@@ -56,18 +65,22 @@ pub async fn query_obsoleted(bodhi: &BodhiClient, release: FedoraRelease) -> Res
     let obsolete_updates = match bodhi.paginated_request(&obsolete_query).await {
         Ok(updates) => updates,
         Err(error) => {
-            return Err(error.to_string());
+            return Err(Error::bodhi_with("Failed to query 'obsolete' updates", error));
         },
     };
 
     Ok(obsolete_updates)
 }
 
-/// This helper function queries updates in "pending" state for a specific release, and prints a
-/// nice progress bar to indicate query progress.
-pub async fn query_pending(bodhi: &BodhiClient, release: FedoraRelease) -> Result<Vec<Update>, String> {
+/// This helper function queries updates in "pending" state for a specific release, and shows its
+/// progress in the given progress display.
+pub async fn query_pending(
+    bodhi: &BodhiClient,
+    release: FedoraRelease,
+    progress: &QueryProgress,
+) -> Result<Vec<Update>, Error> {
     let pending = "Updates (pending)";
-    let pending_progress = |p, ps| progress_bar(pending, p, ps);
+    let pending_progress = |p, ps| progress.update(pending, p, ps);
 
     let releases = vec![release];
     let pending_query = bodhi::query::UpdateQuery::new()
@@ -79,18 +92,22 @@ pub async fn query_pending(bodhi: &BodhiClient, release: FedoraRelease) -> Resul
     let pending_updates = match bodhi.paginated_request(&pending_query).await {
         Ok(updates) => updates,
         Err(error) => {
-            return Err(error.to_string());
+            return Err(Error::bodhi_with("Failed to query 'pending' updates", error));
         },
     };
 
     Ok(pending_updates)
 }
 
-/// This helper function queries updates in "unpushed" state for a specific release, and prints a
-/// nice progress bar to indicate query progress.
-pub async fn query_unpushed(bodhi: &BodhiClient, release: FedoraRelease) -> Result<Vec<Update>, String> {
+/// This helper function queries updates in "unpushed" state for a specific release, and shows its
+/// progress in the given progress display.
+pub async fn query_unpushed(
+    bodhi: &BodhiClient,
+    release: FedoraRelease,
+    progress: &QueryProgress,
+) -> Result<Vec<Update>, Error> {
     let unpushed = "Updates (unpushed)";
-    let unpushed_progress = |p, ps| progress_bar(unpushed, p, ps);
+    let unpushed_progress = |p, ps| progress.update(unpushed, p, ps);
 
     let releases = vec![release];
     let unpushed_query = bodhi::query::UpdateQuery::new()
@@ -102,7 +119,7 @@ pub async fn query_unpushed(bodhi: &BodhiClient, release: FedoraRelease) -> Resu
     let unpushed_updates = match bodhi.paginated_request(&unpushed_query).await {
         Ok(updates) => updates,
         Err(error) => {
-            return Err(error.to_string());
+            return Err(Error::bodhi_with("Failed to query 'unpushed' updates", error));
         },
     };
 
@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use bodhi::error::QueryError;
+use bodhi::{BodhiClient, BugFeedbackData, CommentCreator, Karma, NewComment, TestCaseFeedbackData, Update};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs::read_to_string;
+use tokio::fs::write;
+
+use crate::drafts::remove_draft;
+use crate::error::Error;
+use crate::history::{save_snapshot, UpdateSnapshot};
+use crate::output::print_server_messages;
+
+const FILE_NAME: &str = "fedora-update-feedback.queue";
+
+/// This struct contains all feedback for a single update that is waiting to be submitted.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QueuedFeedback {
+    /// alias of the update this feedback is for
+    pub alias: String,
+    /// title of the update this feedback is for
+    pub title: String,
+    /// date & time when this feedback was recorded
+    pub recorded: DateTime<Utc>,
+    /// comment text (can be multiple lines)
+    pub comment: Option<String>,
+    /// feedback karma
+    pub karma: Karma,
+    /// list of bug feedback items (if any)
+    pub bug_feedback: Vec<(u32, Karma)>,
+    /// list of testcase feedback items (if any)
+    pub testcase_feedback: Vec<(String, Karma)>,
+    /// state of the update at the time this feedback was recorded
+    #[serde(default)]
+    pub snapshot: Option<UpdateSnapshot>,
+}
+
+impl QueuedFeedback {
+    /// This method constructs a queue entry from the feedback values that were provided for an
+    /// update.
+    pub fn new(
+        update: &Update,
+        comment: Option<String>,
+        karma: Karma,
+        bug_feedback: Vec<(u32, Karma)>,
+        testcase_feedback: Vec<(&str, Karma)>,
+    ) -> QueuedFeedback {
+        QueuedFeedback {
+            alias: update.alias.clone(),
+            title: update.title.clone(),
+            recorded: Utc::now(),
+            comment,
+            karma,
+            bug_feedback,
+            testcase_feedback: testcase_feedback
+                .into_iter()
+                .map(|(name, karma)| (name.to_string(), karma))
+                .collect(),
+            snapshot: Some(UpdateSnapshot::of(update)),
+        }
+    }
+}
+
+/// This struct contains the list of feedback items that have not been submitted yet.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct FeedbackQueue {
+    /// feedback items in the order in which they were recorded
+    pub entries: Vec<QueuedFeedback>,
+}
+
+fn get_queue_path() -> Result<PathBuf, Error> {
+    let data_dir = dirs::data_dir().ok_or_else(|| Error::config("Unable to determine the data directory."))?;
+    Ok(data_dir.join(FILE_NAME))
+}
+
+/// Helper function to get the list of queued feedback from the data file.
+pub async fn get_queued() -> Result<FeedbackQueue, Error> {
+    let queue_path = get_queue_path()?;
+
+    let string = match read_to_string(&queue_path).await {
+        Ok(string) => string,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(FeedbackQueue::default()),
+        Err(error) => return Err(Error::io(format!("Failed to read {}", queue_path.display()), error)),
+    };
+
+    serde_json::from_str(&string)
+        .map_err(|error| Error::parse_with(format!("Failed to parse {}", queue_path.display()), error))
+}
+
+/// Helper function to write the list of queued feedback to the data file.
+pub async fn set_queued(queue: &FeedbackQueue) -> Result<(), Error> {
+    let queue_path = get_queue_path()?;
+
+    let contents = serde_json::to_string_pretty(queue)
+        .map_err(|error| Error::parse_with("Failed to serialize queued feedback", error))?;
+    write(&queue_path, contents)
+        .await
+        .map_err(|error| Error::io(format!("Failed to write {}", queue_path.display()), error))?;
+
+    Ok(())
+}
+
+/// Helper function to add feedback to the end of the queue and write it to disk immediately.
+pub async fn push_queued(feedback: QueuedFeedback) -> Result<(), Error> {
+    let mut queue = get_queued().await?;
+    queue.entries.push(feedback);
+    set_queued(&queue).await
+}
+
+/// This helper function submits feedback for an update to bodhi. If submitting feedback was
+/// successful, the state of the update at the time the feedback was recorded is remembered, so
+/// changes can be shown when the update is edited later.
+pub async fn submit_feedback(bodhi: &BodhiClient, feedback: &QueuedFeedback) -> Result<NewComment, QueryError> {
+    let mut builder = CommentCreator::new(&feedback.alias).karma(feedback.karma);
+
+    if let Some(text) = &feedback.comment {
+        builder = builder.text(text);
+    };
+
+    let bug_feedbacks: Vec<BugFeedbackData> = feedback
+        .bug_feedback
+        .iter()
+        .map(|(id, karma)| BugFeedbackData::new(*id, *karma))
+        .collect();
+    builder = builder.bug_feedback(&bug_feedbacks);
+
+    let testcase_feedbacks: Vec<TestCaseFeedbackData> = feedback
+        .testcase_feedback
+        .iter()
+        .map(|(name, karma)| TestCaseFeedbackData::new(name, *karma))
+        .collect();
+    builder = builder.testcase_feedback(&testcase_feedbacks);
+
+    let result = bodhi.request(&builder).await?;
+
+    // the draft comment is no longer needed once feedback was submitted
+    remove_draft(&feedback.alias).await;
+
+    if let Some(snapshot) = &feedback.snapshot {
+        let snapshot = UpdateSnapshot {
+            submitted: Utc::now(),
+            ..snapshot.clone()
+        };
+
+        if let Err(error) = save_snapshot(&feedback.alias, snapshot).await {
+            println!("WARNING: {}", error.report());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Helper function to save feedback in the local queue, so it can be submitted later.
+pub async fn save_feedback(feedback: QueuedFeedback) {
+    let comment = feedback.comment.clone();
+
+    match push_queued(feedback).await {
+        Ok(()) => {
+            println!("Feedback was saved in the local queue.");
+            println!("It can be submitted later with the \"submit\" subcommand.");
+        },
+        Err(error) => {
+            println!("Failed to save feedback in the local queue: {}", error);
+            if let Some(comment) = comment {
+                println!("The comment text was:");
+                println!("{}", comment.trim());
+            }
+        },
+    }
+}
+
+/// This helper function submits all queued feedback to bodhi and reports the result for every
+/// entry. Entries that could not be submitted are kept in the queue.
+///
+/// Entries are removed from the queue on disk right after they were submitted, so feedback is not
+/// submitted twice if the program is interrupted while working through the queue.
+pub async fn submit_queued(bodhi: &BodhiClient) -> Result<(), Error> {
+    let mut queue = get_queued().await?;
+
+    if queue.entries.is_empty() {
+        println!("No feedback is waiting in the local queue.");
+        return Ok(());
+    }
+
+    let total = queue.entries.len();
+    let mut failed: usize = 0;
+
+    for number in 0..total {
+        // entries that were submitted successfully have already been removed
+        let feedback = &queue.entries[failed];
+
+        println!("[{}/{}] {}: {}", number + 1, total, &feedback.alias, &feedback.title);
+        println!("Recorded: {}", feedback.recorded.format("%Y-%m-%d %H:%M"));
+
+        match submit_feedback(bodhi, feedback).await {
+            Ok(value) => {
+                println!("Comment created.");
+                print_server_messages(&value.caveats);
+
+                queue.entries.remove(failed);
+                set_queued(&queue).await?;
+            },
+            Err(error) => {
+                println!("Failed to submit feedback: {}", error);
+                failed += 1;
+            },
+        }
+
+        println!();
+    }
+
+    println!("Submitted: {}, Failed: {}", total - failed, failed);
+
+    if failed > 0 {
+        println!("Feedback that could not be submitted was kept in the local queue.");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::nvr::NVR;
+use crate::sysinfo::{InstalledPackages, RunningSystem};
+
+// source packages that are only used after the system has been rebooted (libraries like mesa are
+// only loaded by applications and graphical sessions, which are covered by checking for processes
+// that still use deleted files instead)
+const REBOOT_PACKAGES: &[&str] = &[
+    "dbus",
+    "dbus-broker",
+    "glibc",
+    "linux-firmware",
+    "microcode_ctl",
+    "systemd",
+];
+
+// source packages that provide the kernel
+const KERNEL_PACKAGES: &[&str] = &["kernel", "kernel-rt"];
+
+/// This enum describes reasons why installed builds from an update are not actually in use yet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RestartReason {
+    /// a kernel from the update is installed, but a different kernel is running
+    KernelNotBooted {
+        /// NVR of the installed kernel build
+        build: String,
+        /// release of the running kernel
+        running: String,
+    },
+    /// a core package was installed after the system was last booted
+    InstalledAfterBoot {
+        /// NVR of the installed build
+        build: String,
+        /// date & time when the system was booted
+        boot_time: DateTime<Utc>,
+    },
+    /// running processes still use files from a previous version of a package
+    ProcessesNotRestarted {
+        /// NVR of the installed build
+        build: String,
+        /// running processes (with their PIDs) that still use deleted files
+        processes: Vec<String>,
+    },
+}
+
+/// This helper function returns the part of a library path that does not change with the version of
+/// the library, for example, `/usr/lib64/libfoo.so` for `/usr/lib64/libfoo.so.1.2.3`.
+fn unversioned(path: &str) -> &str {
+    match path.find(".so.") {
+        Some(index) => &path[..index + 3],
+        None => path,
+    }
+}
+
+/// This function determines why installed builds from an update are not in use by the running
+/// system yet.
+///
+/// Kernels need to be booted, core packages (like `glibc` or `systemd`) need a reboot if they were
+/// installed after the system was booted, and processes that still use deleted files from a
+/// previous version of a package need to be restarted.
+pub fn restart_reasons(
+    builds: &[String],
+    installed: &InstalledPackages,
+    running: &RunningSystem,
+    files: &HashMap<String, Vec<String>>,
+) -> Vec<RestartReason> {
+    let mut reasons: Vec<RestartReason> = Vec::new();
+
+    for build in builds {
+        let Ok(nvr) = build.parse::<NVR>() else {
+            continue;
+        };
+        let binaries = installed.src_bin_map.get(build).map(Vec::as_slice).unwrap_or_default();
+
+        if KERNEL_PACKAGES.contains(&nvr.n.as_str()) {
+            if let Some(kernel) = &running.kernel {
+                if !kernel.starts_with(&format!("{}-{}", nvr.v, nvr.r)) {
+                    reasons.push(RestartReason::KernelNotBooted {
+                        build: build.clone(),
+                        running: kernel.clone(),
+                    });
+                }
+            }
+            continue;
+        }
+
+        if REBOOT_PACKAGES.contains(&nvr.n.as_str()) {
+            let install_time = binaries
+                .iter()
+                .filter_map(|binary| installed.install_times.get(binary))
+                .max();
+
+            if let (Some(install_time), Some(boot_time)) = (install_time, running.boot_time) {
+                if *install_time > boot_time {
+                    reasons.push(RestartReason::InstalledAfterBoot {
+                        build: build.clone(),
+                        boot_time,
+                    });
+                    continue;
+                }
+            }
+        }
+
+        let mut processes: Vec<String> = Vec::new();
+        for path in binaries.iter().filter_map(|binary| files.get(binary)).flatten() {
+            for (deleted, users) in &running.deleted_files {
+                if deleted == path || (deleted.contains(".so") && unversioned(deleted) == unversioned(path)) {
+                    processes.extend(users.iter().cloned());
+                }
+            }
+        }
+        processes.sort();
+        processes.dedup();
+
+        if !processes.is_empty() {
+            reasons.push(RestartReason::ProcessesNotRestarted {
+                build: build.clone(),
+                processes,
+            });
+        }
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn installed() -> InstalledPackages {
+        let time = |string: &str| string.parse::<DateTime<Utc>>().unwrap();
+
+        InstalledPackages {
+            release: "F40".parse().unwrap(),
+            packages: Vec::new(),
+            src_bin_map: HashMap::from([
+                (
+                    String::from("kernel-6.9.4-200.fc40"),
+                    vec![String::from("kernel-core-6.9.4-200.fc40.x86_64")],
+                ),
+                (
+                    String::from("glibc-2.39-15.fc40"),
+                    vec![String::from("glibc-2.39-15.fc40.x86_64")],
+                ),
+                (
+                    String::from("foo-1.2-1.fc40"),
+                    vec![String::from("foo-libs-1.2-1.fc40.x86_64")],
+                ),
+            ]),
+            summaries: HashMap::new(),
+            install_times: HashMap::from([
+                (
+                    String::from("kernel-core-6.9.4-200.fc40.x86_64"),
+                    time("2024-06-10T10:00:00Z"),
+                ),
+                (String::from("glibc-2.39-15.fc40.x86_64"), time("2024-06-10T10:00:00Z")),
+                (String::from("foo-libs-1.2-1.fc40.x86_64"), time("2024-06-10T10:00:00Z")),
+            ]),
+            staged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reboot() {
+        let builds = vec![
+            String::from("kernel-6.9.4-200.fc40"),
+            String::from("glibc-2.39-15.fc40"),
+        ];
+        let files = HashMap::new();
+
+        // booted before the update was installed
+        let running = RunningSystem {
+            boot_time: Some("2024-06-09T08:00:00Z".parse().unwrap()),
+            kernel: Some(String::from("6.8.11-300.fc40.x86_64")),
+            deleted_files: HashMap::new(),
+            hardware: None,
+        };
+        let reasons = restart_reasons(&builds, &installed(), &running, &files);
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(
+            reasons[0],
+            RestartReason::KernelNotBooted {
+                build: String::from("kernel-6.9.4-200.fc40"),
+                running: String::from("6.8.11-300.fc40.x86_64"),
+            }
+        );
+        assert!(matches!(reasons[1], RestartReason::InstalledAfterBoot { .. }));
+
+        // booted after the update was installed
+        let running = RunningSystem {
+            boot_time: Some("2024-06-10T12:00:00Z".parse().unwrap()),
+            kernel: Some(String::from("6.9.4-200.fc40.x86_64")),
+            deleted_files: HashMap::new(),
+            hardware: None,
+        };
+        assert!(restart_reasons(&builds, &installed(), &running, &files).is_empty());
+    }
+
+    #[test]
+    fn processes() {
+        let builds = vec![String::from("foo-1.2-1.fc40")];
+        let files = HashMap::from([(
+            String::from("foo-libs-1.2-1.fc40.x86_64"),
+            vec![String::from("/usr/lib64/libfoo.so.1.2.0")],
+        )]);
+
+        let running = RunningSystem {
+            boot_time: None,
+            kernel: None,
+            deleted_files: HashMap::from([
+                (
+                    String::from("/usr/lib64/libfoo.so.1.1.0"),
+                    vec![String::from("foo (1234)"), String::from("bar (2345)")],
+                ),
+                (
+                    String::from("/usr/lib64/libother.so.1"),
+                    vec![String::from("baz (3456)")],
+                ),
+            ]),
+            hardware: None,
+        };
+
+        assert_eq!(
+            restart_reasons(&builds, &installed(), &running, &files),
+            vec![RestartReason::ProcessesNotRestarted {
+                build: String::from("foo-1.2-1.fc40"),
+                processes: vec![String::from("bar (2345)"), String::from("foo (1234)")],
+            }]
+        );
+    }
+
+    #[test]
+    fn library_names() {
+        assert_eq!(unversioned("/usr/lib64/libfoo.so.1.2.3"), "/usr/lib64/libfoo.so");
+        assert_eq!(unversioned("/usr/lib64/libfoo.so"), "/usr/lib64/libfoo.so");
+        assert_eq!(unversioned("/usr/bin/foo"), "/usr/bin/foo");
+    }
+}
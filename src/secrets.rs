@@ -2,9 +2,25 @@ use std::collections::HashMap;
 
 use secret_service::{Collection, EncryptionType, SecretService};
 
+use crate::error::Error;
+
+// environment variable that can be used to provide the FAS password non-interactively
+const PASSWORD_VAR: &str = "FUF_PASSWORD";
+
+/// This function returns the FAS password from the `FUF_PASSWORD` environment variable, if set.
+pub fn password_from_env() -> Option<String> {
+    std::env::var(PASSWORD_VAR).ok().filter(|password| !password.is_empty())
+}
+
 /// This function prompts the user for their FAS password.
-pub fn read_password() -> String {
-    rpassword::prompt_password("FAS Password: ").expect("Failed to read password from stdin.")
+pub fn read_password() -> Result<String, Error> {
+    rpassword::prompt_password("FAS Password: ").map_err(|error| Error::io("Failed to read password", error))
+}
+
+/// This function prints a warning for a problem with the session keyring. These problems are not
+/// fatal, since the password can always be entered manually instead.
+fn warn(error: Error) {
+    println!("WARNING: {}", error.report());
 }
 
 /// This function stores the password in the session keyring.
@@ -18,25 +34,25 @@ async fn store_password(
         .create_item("bodhi-cli", attributes.clone(), password, replace, "password")
         .await
     {
-        println!("Failed to save password with SecretService: {}", error);
+        warn(Error::keyring("Failed to save password with SecretService", error));
     }
 }
 
 /// This function asks for and stores the password in the session keyring.
-pub(crate) async fn get_store_password(clear: bool) -> Result<String, String> {
+pub(crate) async fn get_store_password(clear: bool) -> Result<String, Error> {
     let ss = match SecretService::connect(EncryptionType::Dh).await {
         Ok(ss) => ss,
         Err(error) => {
-            println!("Failed to initialize SecretService client: {}", error);
-            return Ok(read_password());
+            warn(Error::keyring("Failed to initialize SecretService client", error));
+            return read_password();
         },
     };
 
     let mut collection = match ss.get_default_collection().await {
         Ok(c) => c,
         Err(error) => {
-            println!("Failed to query SecretService: {}", error);
-            return Ok(read_password());
+            warn(Error::keyring("Failed to query SecretService", error));
+            return read_password();
         },
     };
 
@@ -46,13 +62,13 @@ pub(crate) async fn get_store_password(clear: bool) -> Result<String, String> {
     let items = match collection.search_items(attributes.clone()).await {
         Ok(items) => items,
         Err(error) => {
-            format!("Failed to query SecretService: {}", error);
-            return Ok(read_password());
+            warn(Error::keyring("Failed to query SecretService", error));
+            return read_password();
         },
     };
 
     if clear {
-        let password = read_password();
+        let password = read_password()?;
         store_password(&mut collection, attributes, password.as_bytes(), true).await;
         return Ok(password);
     };
@@ -63,20 +79,20 @@ pub(crate) async fn get_store_password(clear: bool) -> Result<String, String> {
                 Ok(valid) => valid,
                 Err(error) => {
                     println!("Stored password was not valid UTF-8: {}", error);
-                    let password = read_password();
+                    let password = read_password()?;
                     store_password(&mut collection, attributes, password.as_bytes(), true).await;
                     password
                 },
             },
             Err(error) => {
-                println!("Password was not stored correctly: {}", error);
-                let password = read_password();
+                warn(Error::keyring("Password was not stored correctly", error));
+                let password = read_password()?;
                 store_password(&mut collection, attributes, password.as_bytes(), true).await;
                 password
             },
         },
         None => {
-            let password = read_password();
+            let password = read_password()?;
             store_password(&mut collection, attributes, password.as_bytes(), false).await;
 
             password
@@ -0,0 +1,116 @@
+use bodhi::Update;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::SoakTimeConfig;
+use crate::filter::packages_in_update;
+
+/// This struct describes an update that has not been installed for long enough to provide positive
+/// karma for it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SoakTime {
+    /// minimum number of days between installing the update and providing positive karma
+    pub required_days: u32,
+    /// time that has passed since the update was installed
+    pub installed_for: Duration,
+    /// whether positive karma is refused (instead of asking for confirmation)
+    pub strict: bool,
+}
+
+/// This function returns the minimum number of days between installing an update and providing
+/// positive karma for it, if any.
+///
+/// If values are configured for multiple packages in the update, the largest value is used.
+pub fn required_days(config: &SoakTimeConfig, update: &Update) -> Option<u32> {
+    let for_packages = packages_in_update(update)
+        .iter()
+        .filter_map(|name| config.packages.get(name))
+        .max()
+        .copied();
+
+    for_packages
+        .or_else(|| config.types.get(&update.update_type.to_string()).copied())
+        .or(config.days)
+}
+
+/// This function checks whether an update has been installed for long enough to provide positive
+/// karma for it. If the minimum time has not passed yet, the required and elapsed time are
+/// returned.
+///
+/// Updates for which the installation time is not known are not checked.
+pub fn check_soak_time(
+    config: &SoakTimeConfig,
+    update: &Update,
+    installed: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<SoakTime> {
+    let required = required_days(config, update)?;
+    let installed_for = now - installed?;
+
+    if installed_for >= Duration::days(required.into()) {
+        return None;
+    }
+
+    Some(SoakTime {
+        required_days: required,
+        installed_for,
+        strict: config.strict.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bodhi::UpdateType;
+
+    use super::*;
+
+    const UPDATES: &str = include_str!("../tests/fixtures/updates.json");
+
+    fn config() -> SoakTimeConfig {
+        SoakTimeConfig {
+            days: Some(1),
+            types: HashMap::from([(String::from("bugfix"), 2)]),
+            packages: HashMap::from([(String::from("quux"), 7), (String::from("qux"), 3)]),
+            strict: None,
+        }
+    }
+
+    #[test]
+    fn days() {
+        let mut updates: Vec<Update> = serde_json::from_str(UPDATES).unwrap();
+        let config = config();
+
+        // the largest value for packages in the update is used
+        assert_eq!(required_days(&config, &updates[2]), Some(7));
+
+        updates[0].update_type = UpdateType::BugFix;
+        assert_eq!(required_days(&config, &updates[0]), Some(2));
+
+        updates[0].update_type = UpdateType::Enhancement;
+        assert_eq!(required_days(&config, &updates[0]), Some(1));
+
+        assert_eq!(required_days(&SoakTimeConfig::default(), &updates[0]), None);
+    }
+
+    #[test]
+    fn check() {
+        let updates: Vec<Update> = serde_json::from_str(UPDATES).unwrap();
+        let config = config();
+
+        let installed: DateTime<Utc> = "2024-05-01T10:00:00Z".parse().unwrap();
+        let now: DateTime<Utc> = "2024-05-04T10:00:00Z".parse().unwrap();
+
+        let soak = check_soak_time(&config, &updates[2], Some(installed), now).unwrap();
+        assert_eq!(soak.required_days, 7);
+        assert_eq!(soak.installed_for, Duration::days(3));
+        assert!(!soak.strict);
+
+        let now: DateTime<Utc> = "2024-05-08T10:00:00Z".parse().unwrap();
+        assert_eq!(check_soak_time(&config, &updates[2], Some(installed), now), None);
+
+        // the installation time is not known
+        assert_eq!(check_soak_time(&config, &updates[2], None, now), None);
+    }
+}
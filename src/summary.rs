@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use bodhi::Karma;
+
+/// This struct keeps track of what happened to the updates that were presented during a review
+/// session, so a summary can be printed at the end.
+#[derive(Debug, Default)]
+pub struct SessionSummary {
+    /// number of updates that feedback was asked for
+    pub reviewed: usize,
+    /// number of updates that were skipped
+    pub skipped: usize,
+    /// number of updates that were ignored
+    pub ignored: usize,
+    /// number of updates whose packages were blocked
+    pub blocked: usize,
+    /// karma of comments that were posted successfully
+    pub posted: Vec<Karma>,
+    /// number of comments that were saved in the local queue
+    pub queued: usize,
+    /// number of comments that could not be submitted (and were saved in the local queue instead)
+    pub failed: usize,
+    /// messages from the server about posted comments, with the update alias
+    pub caveats: Vec<(String, String)>,
+}
+
+impl SessionSummary {
+    /// This method records a comment that was posted successfully, including any messages from the
+    /// server about it.
+    pub fn record_posted(&mut self, alias: &str, karma: Karma, caveats: &[HashMap<String, String>]) {
+        self.posted.push(karma);
+
+        for caveat in caveats {
+            for value in caveat.values() {
+                self.caveats.push((alias.to_string(), value.clone()));
+            }
+        }
+    }
+}
+
+/// This helper function formats the number of comments with each karma value, for example,
+/// "2 × +1, 1 × 0".
+fn karma_breakdown(posted: &[Karma]) -> String {
+    let values = [(Karma::Positive, "+1"), (Karma::Neutral, "0"), (Karma::Negative, "-1")];
+
+    values
+        .iter()
+        .filter_map(|(karma, label)| {
+            let count = posted.iter().filter(|value| *value == karma).count();
+            (count > 0).then(|| format!("{} × {}", count, label))
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// This function formats the summary of a review session as a table.
+pub fn format_summary(summary: &SessionSummary) -> String {
+    let posted = if summary.posted.is_empty() {
+        String::from("0")
+    } else {
+        format!("{} (karma: {})", summary.posted.len(), karma_breakdown(&summary.posted))
+    };
+
+    let mut lines = vec![
+        String::from("Summary:"),
+        format!("  Updates reviewed:  {}", summary.reviewed),
+        format!("  Skipped:           {}", summary.skipped),
+        format!("  Ignored:           {}", summary.ignored),
+        format!("  Blocked:           {}", summary.blocked),
+        format!("  Comments posted:   {}", posted),
+    ];
+
+    if summary.queued > 0 {
+        lines.push(format!("  Saved in queue:    {}", summary.queued));
+    }
+    if summary.failed > 0 {
+        lines.push(format!(
+            "  Failed:            {} (saved in the local queue)",
+            summary.failed
+        ));
+    }
+
+    if !summary.caveats.is_empty() {
+        lines.push(String::from("Server messages:"));
+        for (alias, message) in &summary.caveats {
+            lines.push(format!("- {}: {}", alias, message));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut summary = SessionSummary {
+            reviewed: 5,
+            skipped: 1,
+            ignored: 1,
+            failed: 1,
+            ..Default::default()
+        };
+        summary.record_posted("FEDORA-2024-0001aaaa", Karma::Positive, &[]);
+        summary.record_posted(
+            "FEDORA-2024-0002bbbb",
+            Karma::Positive,
+            &[HashMap::from([(
+                String::from("description"),
+                String::from("You may not give karma to your own updates."),
+            )])],
+        );
+        summary.record_posted("FEDORA-2024-0003cccc", Karma::Negative, &[]);
+
+        assert_eq!(
+            format_summary(&summary),
+            "Summary:\n\
+             \x20 Updates reviewed:  5\n\
+             \x20 Skipped:           1\n\
+             \x20 Ignored:           1\n\
+             \x20 Blocked:           0\n\
+             \x20 Comments posted:   3 (karma: 2 × +1, 1 × -1)\n\
+             \x20 Failed:            1 (saved in the local queue)\n\
+             Server messages:\n\
+             - FEDORA-2024-0002bbbb: You may not give karma to your own updates."
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let summary = format_summary(&SessionSummary::default());
+        assert!(summary.ends_with("  Comments posted:   0"));
+        assert!(!summary.contains("Failed"));
+    }
+}
@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+
+use bodhi::FedoraRelease;
+use chrono::{DateTime, Utc};
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+use super::proc::{query_running_system, query_system_details};
+use super::rpm::{
+    installation_times,
+    installed_sources,
+    parse_packages,
+    query_changelog,
+    query_files,
+    src_bin_map,
+    summaries,
+    RpmPackage,
+    QUERY_FORMAT,
+};
+use super::{query_release, run_command, Inventory, RunningSystem};
+use crate::changelog::ChangelogEntry;
+use crate::error::Error;
+use crate::nvr::NVR;
+use crate::parse::parse_nevra;
+
+/// This enum represents the major versions of `dnf` that are supported.
+///
+/// Command line arguments, query format strings, and output formats differ between dnf4 (default
+/// up to Fedora 40) and dnf5 (default since Fedora 41).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DnfVersion {
+    /// dnf 4.x (Python implementation)
+    Dnf4,
+    /// dnf 5.x (C++ implementation)
+    Dnf5,
+}
+
+/// This helper function determines the major version of `dnf` from the output of `dnf --version`.
+///
+/// dnf5 prints `dnf5 version 5.2.6.2` on the first line, while dnf4 prints only the plain version
+/// number.
+pub fn parse_dnf_version(output: &str) -> DnfVersion {
+    let first = output.trim().lines().next().unwrap_or_default().trim();
+
+    if first.starts_with("dnf5") || first.starts_with("5.") {
+        DnfVersion::Dnf5
+    } else {
+        DnfVersion::Dnf4
+    }
+}
+
+/// This helper function parses the output of `dnf repolist --enabled updates-testing`.
+///
+/// Depending on the version of dnf, the output can contain a header line even if there were no
+/// matching repositories, so check whether any line starts with the repository ID instead of
+/// checking for empty output.
+fn parse_repolist(output: &str) -> bool {
+    output
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some("updates-testing"))
+}
+
+/// This helper function parses the output of `dnf repoquery` with the
+/// `%{name}\t%{version}-%{release}` query format into a map from package names to available
+/// versions.
+fn parse_versions(output: &str) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+
+    for line in output.lines() {
+        if let Some((name, version)) = line.trim().split_once('\t') {
+            let entry = versions.entry(name.to_string()).or_default();
+            if !entry.contains(&version.to_string()) {
+                entry.push(version.to_string());
+            }
+        }
+    }
+
+    versions
+}
+
+/// This helper function parses the output of `dnf history info` into a map from the given package
+/// names to the versions (as `VERSION-RELEASE` strings) that were installed, upgraded, or removed
+/// by the listed transactions.
+///
+/// Only the "Packages Altered" sections are considered, since their layout differs between dnf4
+/// and dnf5, but both contain one package NEVRA per line.
+fn parse_history(output: &str, names: &[String]) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    let mut altered = false;
+
+    for line in output.lines() {
+        if line.to_lowercase().starts_with("packages altered:") {
+            altered = true;
+            continue;
+        }
+
+        // sections end with a line that is not indented
+        if !line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+            altered = false;
+            continue;
+        }
+
+        if !altered {
+            continue;
+        }
+
+        for word in line.split_whitespace() {
+            let Ok((name, _, version, release, _)) = parse_nevra(word) else {
+                continue;
+            };
+
+            if names.iter().any(|n| n == name) {
+                let version = format!("{}-{}", version, release);
+                let entry = versions.entry(name.to_string()).or_default();
+                if !entry.contains(&version) {
+                    entry.push(version);
+                }
+            }
+        }
+    }
+
+    versions
+}
+
+/// This struct provides information about installed packages by querying `dnf` and `rpm`.
+///
+/// All information about installed packages is read with one combined query when it is first
+/// needed, since every query needs to load the whole RPM database, which can take several seconds.
+#[derive(Debug)]
+pub struct DnfInventory {
+    version: DnfVersion,
+    packages: OnceCell<Vec<RpmPackage>>,
+}
+
+impl DnfInventory {
+    /// This method constructs a new [`DnfInventory`] for the given version of `dnf`.
+    pub fn new(version: DnfVersion) -> DnfInventory {
+        DnfInventory {
+            version,
+            packages: OnceCell::new(),
+        }
+    }
+
+    /// This method constructs a new [`DnfInventory`] for the version of `dnf` that is installed
+    /// on the current system.
+    pub async fn detect() -> Result<DnfInventory, Error> {
+        let output = run_command(Command::new("dnf").arg("--version"), "dnf --version").await?;
+        Ok(DnfInventory::new(parse_dnf_version(&output)))
+    }
+
+    /// This helper method runs `dnf repoquery` for installed packages with the given query format.
+    async fn repoquery(&self, queryformat: &str) -> Result<String, Error> {
+        let queryformat = match self.version {
+            DnfVersion::Dnf4 => queryformat.to_string(),
+            // dnf5 does not terminate query results with newline characters
+            DnfVersion::Dnf5 => format!("{}\n", queryformat),
+        };
+
+        run_command(
+            Command::new("dnf")
+                .arg("--quiet")
+                .arg("repoquery")
+                .arg("--cacheonly")
+                .arg("--installed")
+                .arg("--queryformat")
+                .arg(queryformat),
+            "dnf repoquery",
+        )
+        .await
+    }
+
+    /// This helper method returns information about all installed packages. The query is only run
+    /// once, and its results are reused for subsequent calls.
+    async fn packages(&self) -> Result<&[RpmPackage], Error> {
+        let packages = self
+            .packages
+            .get_or_try_init(|| async {
+                let results = self.repoquery(QUERY_FORMAT).await?;
+                parse_packages(&results)
+            })
+            .await?;
+
+        Ok(packages)
+    }
+
+    /// This helper method queries `dnf` for versions of packages that are available from
+    /// repositories other than "updates-testing", i.e. versions that are currently considered
+    /// stable.
+    async fn stable_versions(&self, names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        if names.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let queryformat = match self.version {
+            DnfVersion::Dnf4 => "%{name}\t%{version}-%{release}",
+            DnfVersion::Dnf5 => "%{name}\t%{version}-%{release}\n",
+        };
+
+        let output = run_command(
+            Command::new("dnf")
+                .arg("--quiet")
+                .arg("repoquery")
+                .arg("--cacheonly")
+                .arg("--available")
+                .arg("--disablerepo=*testing*")
+                .arg("--queryformat")
+                .arg(queryformat)
+                .args(names),
+            "dnf repoquery",
+        )
+        .await?;
+
+        Ok(parse_versions(&output))
+    }
+
+    /// This helper method queries the `dnf` history for versions of packages that were installed
+    /// previously. Versions that are currently installed are not included.
+    async fn history_versions(&self, names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        let mut command = Command::new("dnf");
+        command.arg("--quiet").arg("history").arg("info");
+
+        match self.version {
+            // dnf4 accepts package names instead of transaction IDs
+            DnfVersion::Dnf4 => command.args(names),
+            DnfVersion::Dnf5 => command.arg(format!("--contains-pkgs={}", names.join(","))),
+        };
+
+        let output = run_command(&mut command, "dnf history info").await?;
+        let mut versions = parse_history(&output, names);
+
+        for package in self.packages().await? {
+            if let Some(previous) = versions.get_mut(&package.name) {
+                let installed = format!("{}-{}", package.version, package.release);
+                previous.retain(|version| version != &installed);
+            }
+        }
+
+        versions.retain(|_, previous| !previous.is_empty());
+        Ok(versions)
+    }
+}
+
+impl Inventory for DnfInventory {
+    /// This method queries RPM for the value of `%{fedora}` on the current system.
+    async fn get_release(&self) -> Result<FedoraRelease, Error> {
+        query_release().await
+    }
+
+    /// This method queries `dnf` whether the "updates-testing" repository is enabled.
+    async fn is_update_testing_enabled(&self) -> Result<bool, Error> {
+        // query dnf for enabled repositories, limiting results to those matching "updates-testing"
+        let output = run_command(
+            Command::new("dnf")
+                .arg("repolist")
+                .arg("--enabled")
+                .arg("updates-testing"),
+            "dnf repolist",
+        )
+        .await?;
+
+        Ok(parse_repolist(&output))
+    }
+
+    /// This method returns the source package names of all currently installed packages.
+    async fn get_installed(&self) -> Result<Vec<NVR>, Error> {
+        installed_sources(self.packages().await?)
+    }
+
+    /// This method returns the `Summary` header of installed packages.
+    async fn get_summaries(&self) -> Result<HashMap<String, String>, Error> {
+        Ok(summaries(self.packages().await?))
+    }
+
+    /// This method returns a map from source -> binary package NVRs for installed packages.
+    async fn get_src_bin_map(&self) -> Result<HashMap<String, Vec<String>>, Error> {
+        src_bin_map(self.packages().await?)
+    }
+
+    /// This method returns a map from binary packages to their installation times.
+    async fn get_installation_times(&self) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+        Ok(installation_times(self.packages().await?))
+    }
+
+    /// This method queries the RPM database for the `%changelog` of an installed package.
+    async fn get_changelog(&self, nvra: &str) -> Result<Vec<ChangelogEntry>, Error> {
+        query_changelog(nvra).await
+    }
+
+    /// This method determines the versions of packages that were installed previously according
+    /// to the `dnf` history. For packages without previously installed versions (or if the history
+    /// cannot be read), `dnf` is queried for versions that are available from repositories other
+    /// than "updates-testing", i.e. versions that are currently considered stable.
+    async fn get_baseline_versions(&self, names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        if names.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // the history is only used to improve changelog excerpts, so failing to read it is not fatal
+        let previous = self.history_versions(names).await.unwrap_or_default();
+
+        let remaining: Vec<String> = names
+            .iter()
+            .filter(|name| !previous.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let mut baseline = self.stable_versions(&remaining).await?;
+        baseline.extend(previous);
+        Ok(baseline)
+    }
+
+    /// This method reads the running kernel and the hardware model from `/proc` and `/sys`.
+    async fn get_system_details(&self) -> Result<RunningSystem, Error> {
+        query_system_details().await
+    }
+
+    /// This method reads information about the running system from `/proc`.
+    async fn get_running_system(&self) -> Result<RunningSystem, Error> {
+        query_running_system().await
+    }
+
+    /// This method queries the RPM database for the files in installed packages.
+    async fn get_files(&self, nvras: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        query_files(nvras).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version() {
+        assert_eq!(
+            parse_dnf_version(include_str!("../../tests/fixtures/dnf4/version.txt")),
+            DnfVersion::Dnf4
+        );
+        assert_eq!(
+            parse_dnf_version(include_str!("../../tests/fixtures/dnf5/version.txt")),
+            DnfVersion::Dnf5
+        );
+    }
+
+    #[test]
+    fn repolist() {
+        assert!(parse_repolist(include_str!("../../tests/fixtures/dnf4/repolist.txt")));
+        assert!(parse_repolist(include_str!("../../tests/fixtures/dnf5/repolist.txt")));
+
+        assert!(!parse_repolist(""));
+        assert!(!parse_repolist(include_str!(
+            "../../tests/fixtures/dnf5/repolist-disabled.txt"
+        )));
+    }
+
+    #[test]
+    fn versions() {
+        let versions = parse_versions("foo\t1.1-1.fc40\nfoo\t1.0-3.fc40\nfoo-libs\t1.1-1.fc40\n\n");
+        assert_eq!(versions.get("foo").unwrap(), &vec!["1.1-1.fc40", "1.0-3.fc40"]);
+        assert_eq!(versions.get("foo-libs").unwrap(), &vec!["1.1-1.fc40"]);
+    }
+
+    #[test]
+    fn history() {
+        let names = vec![String::from("foo"), String::from("foo-libs")];
+
+        let dnf4 = parse_history(include_str!("../../tests/fixtures/dnf4/history.txt"), &names);
+        assert_eq!(
+            dnf4.get("foo").unwrap(),
+            &vec!["1.2-1.fc40", "1.1-1.fc40", "1.0-3.fc40"]
+        );
+        assert_eq!(
+            dnf4.get("foo-libs").unwrap(),
+            &vec!["1.2-1.fc40", "1.1-1.fc40", "1.0-3.fc40"]
+        );
+        assert!(!dnf4.contains_key("bar"));
+
+        let dnf5 = parse_history(include_str!("../../tests/fixtures/dnf5/history.txt"), &names);
+        assert_eq!(
+            dnf5.get("foo").unwrap(),
+            &vec!["1.2-1.fc41", "1.1-1.fc41", "1.0-3.fc41"]
+        );
+        assert!(!dnf5.contains_key("foo-libs"));
+    }
+
+    #[test]
+    fn packages() {
+        let dnf4 = parse_packages(include_str!("../../tests/fixtures/dnf4/packages.txt")).unwrap();
+
+        let installed = installed_sources(&dnf4).unwrap();
+        assert_eq!(installed.len(), 4);
+        assert!(installed.contains(&"glibc-2.39-22.fc40".parse().unwrap()));
+        assert!(installed.contains(&"java-21-openjdk-21.0.4.0.7-2.fc40".parse().unwrap()));
+
+        assert_eq!(summaries(&dnf4).get("dnf").unwrap(), "Package manager");
+        assert_eq!(
+            src_bin_map(&dnf4).unwrap().get("dnf-4.21.1-1.fc40").unwrap(),
+            &vec!["dnf-4.21.1-1.fc40.noarch", "python3-dnf-4.21.1-1.fc40.noarch"]
+        );
+        assert_eq!(
+            installation_times(&dnf4)
+                .get("glibc-2.39-22.fc40.x86_64")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-10-01T18:30:00+00:00"
+        );
+
+        let dnf5 = parse_packages(include_str!("../../tests/fixtures/dnf5/packages.txt")).unwrap();
+
+        let installed = installed_sources(&dnf5).unwrap();
+        assert_eq!(installed.len(), 4);
+        assert!(installed.contains(&"dnf5-5.2.6.2-1.fc41".parse().unwrap()));
+
+        assert_eq!(
+            summaries(&dnf5).get("glibc-common").unwrap(),
+            "Common binaries and locale data for glibc"
+        );
+        assert_eq!(
+            src_bin_map(&dnf5).unwrap().get("glibc-2.40-9.fc41").unwrap(),
+            &vec![
+                "glibc-2.40-9.fc41.x86_64",
+                "glibc-common-2.40-9.fc41.x86_64",
+                "glibc-langpack-en-2.40-9.fc41.x86_64"
+            ]
+        );
+        assert_eq!(
+            installation_times(&dnf5)
+                .get("bash-5.2.32-1.fc41.x86_64")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-10-22T08:00:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use bodhi::{FedoraRelease, InvalidValueError};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::fs::read_to_string;
+
+use super::{Inventory, RunningSystem};
+use crate::changelog::{parse_changelog, ChangelogEntry};
+use crate::error::Error;
+use crate::nvr::NVR;
+
+/// This struct provides canned information about installed packages, which is read from a JSON
+/// file instead of querying the current system. This is useful for testing filtering logic on
+/// systems where `dnf` and `rpm` are not available. The file should look something like this:
+///
+/// ```json
+/// {
+///   "release": "F40",
+///   "updates-testing": true,
+///   "installed": ["foo-1.2-1.fc40"],
+///   "src-bin-map": { "foo-1.2-1.fc40": ["foo-1.2-1.fc40.x86_64"] },
+///   "summaries": { "foo": "The foo program" },
+///   "install-times": { "foo-1.2-1.fc40.x86_64": "2024-05-02T10:00:00Z" },
+///   "staged": ["bar-2.0-1.fc40"],
+///   "changelogs": { "foo-1.2-1.fc40.x86_64": "* Mon May 06 2024 Jane Doe - 1.2-1\n- Update to 1.2\n" },
+///   "baseline": { "foo": ["1.1-1.fc40"] },
+///   "running": { "boot-time": "2024-05-01T08:00:00Z", "kernel": "6.8.9-300.fc40.x86_64" },
+///   "files": { "foo-1.2-1.fc40.x86_64": ["/usr/bin/foo"] }
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FixtureInventory {
+    release: String,
+    updates_testing: bool,
+    installed: Vec<String>,
+    #[serde(default)]
+    src_bin_map: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    summaries: HashMap<String, String>,
+    #[serde(default)]
+    install_times: HashMap<String, DateTime<Utc>>,
+    #[serde(default)]
+    staged: Vec<String>,
+    #[serde(default)]
+    changelogs: HashMap<String, String>,
+    #[serde(default)]
+    baseline: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    running: RunningSystem,
+    #[serde(default)]
+    files: HashMap<String, Vec<String>>,
+}
+
+impl FixtureInventory {
+    /// This method parses canned inventory data from a JSON string.
+    pub fn from_json(string: &str) -> Result<FixtureInventory, Error> {
+        serde_json::from_str(string).map_err(|error| Error::parse_with("Failed to parse inventory data", error))
+    }
+
+    /// This method reads canned inventory data from a JSON file.
+    pub async fn from_file(path: &Path) -> Result<FixtureInventory, Error> {
+        let string = read_to_string(path)
+            .await
+            .map_err(|error| Error::io(format!("Unable to read {}", path.display()), error))?;
+
+        FixtureInventory::from_json(&string)
+    }
+}
+
+impl Inventory for FixtureInventory {
+    async fn get_release(&self) -> Result<FedoraRelease, Error> {
+        self.release
+            .parse()
+            .map_err(|error: InvalidValueError| Error::parse_with("Invalid Fedora release in inventory data", error))
+    }
+
+    async fn is_update_testing_enabled(&self) -> Result<bool, Error> {
+        Ok(self.updates_testing)
+    }
+
+    async fn get_installed(&self) -> Result<Vec<NVR>, Error> {
+        self.installed.iter().map(|nvr| nvr.parse()).collect()
+    }
+
+    async fn get_src_bin_map(&self) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(self.src_bin_map.clone())
+    }
+
+    async fn get_summaries(&self) -> Result<HashMap<String, String>, Error> {
+        Ok(self.summaries.clone())
+    }
+
+    async fn get_installation_times(&self) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+        Ok(self.install_times.clone())
+    }
+
+    async fn get_staged(&self) -> Result<Vec<NVR>, Error> {
+        self.staged.iter().map(|nvr| nvr.parse()).collect()
+    }
+
+    async fn get_changelog(&self, nvra: &str) -> Result<Vec<ChangelogEntry>, Error> {
+        Ok(self
+            .changelogs
+            .get(nvra)
+            .map(|text| parse_changelog(text))
+            .unwrap_or_default())
+    }
+
+    async fn get_baseline_versions(&self, names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(self
+            .baseline
+            .iter()
+            .filter(|(name, _)| names.contains(name))
+            .map(|(name, versions)| (name.clone(), versions.clone()))
+            .collect())
+    }
+
+    async fn get_system_details(&self) -> Result<RunningSystem, Error> {
+        Ok(RunningSystem {
+            deleted_files: HashMap::new(),
+            ..self.running.clone()
+        })
+    }
+
+    async fn get_running_system(&self) -> Result<RunningSystem, Error> {
+        Ok(self.running.clone())
+    }
+
+    async fn get_files(&self, nvras: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(self
+            .files
+            .iter()
+            .filter(|(nvra, _)| nvras.contains(nvra))
+            .map(|(nvra, files)| (nvra.clone(), files.clone()))
+            .collect())
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use bodhi::{FedoraRelease, InvalidValueError};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use crate::changelog::ChangelogEntry;
+use crate::error::Error;
+use crate::nvr::NVR;
+
+mod dnf;
+mod fixture;
+mod ostree;
+mod proc;
+mod rpm;
+
+pub use dnf::{parse_dnf_version, DnfInventory, DnfVersion};
+pub use fixture::FixtureInventory;
+pub use ostree::OstreeInventory;
+
+// this file is present on systems that were booted from an ostree deployment
+const OSTREE_BOOTED: &str = "/run/ostree-booted";
+
+/// This helper function runs a command and returns its standard output. If the command fails, the
+/// returned error contains its exit code and standard error output.
+async fn run_command(command: &mut Command, name: &str) -> Result<String, Error> {
+    let output = command
+        .output()
+        .await
+        .map_err(|error| Error::io(format!("Failed to run {}", name), error))?;
+
+    Error::check_output(name, &output)?;
+
+    String::from_utf8(output.stdout)
+        .map_err(|error| Error::parse_with(format!("Output of {} is not valid UTF-8", name), error))
+}
+
+/// This helper function queries RPM for the value of `%{fedora}` on the current system.
+async fn query_release() -> Result<FedoraRelease, Error> {
+    // use RPM to expand the `%{fedora}` macro
+    let output = run_command(Command::new("rpm").arg("--eval").arg("%{fedora}"), "rpm --eval").await?;
+
+    let release = format!("F{}", output.trim());
+
+    let release: FedoraRelease = release.parse().map_err(|error: InvalidValueError| {
+        Error::parse_with(format!("Failed to determine the Fedora release ({})", release), error)
+    })?;
+
+    Ok(release)
+}
+
+/// This struct contains information about the running system that is used to determine whether
+/// installed updates are actually in use yet.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RunningSystem {
+    /// date & time when the system was booted
+    #[serde(default)]
+    pub boot_time: Option<DateTime<Utc>>,
+    /// release of the running kernel (as printed by `uname -r`)
+    #[serde(default)]
+    pub kernel: Option<String>,
+    /// map from deleted files that are still in use to the processes that use them
+    #[serde(default)]
+    pub deleted_files: HashMap<String, Vec<String>>,
+    /// vendor and model of the hardware
+    #[serde(default)]
+    pub hardware: Option<String>,
+}
+
+/// This trait describes a source of information about the current system and the packages that are
+/// installed on it.
+// the returned futures are only awaited from the tokio runtime of the binary, so "Send" bounds are
+// not necessary
+#[allow(async_fn_in_trait)]
+pub trait Inventory {
+    /// This method returns the Fedora release of the current system.
+    async fn get_release(&self) -> Result<FedoraRelease, Error>;
+
+    /// This method returns whether the "updates-testing" repository is enabled.
+    async fn is_update_testing_enabled(&self) -> Result<bool, Error>;
+
+    /// This method returns the source package NVRs of all currently installed packages.
+    async fn get_installed(&self) -> Result<Vec<NVR>, Error>;
+
+    /// This method returns a map from source -> binary package NVRs for installed packages.
+    async fn get_src_bin_map(&self) -> Result<HashMap<String, Vec<String>>, Error>;
+
+    /// This method returns a map from package names to the `Summary` header of installed packages.
+    async fn get_summaries(&self) -> Result<HashMap<String, String>, Error>;
+
+    /// This method returns a map from binary packages to their installation times.
+    async fn get_installation_times(&self) -> Result<HashMap<String, DateTime<Utc>>, Error>;
+
+    /// This method returns the source package NVRs of packages that are only present in a staged
+    /// deployment, i.e. that will only be available after the next reboot. This is only relevant
+    /// for image-based systems like Fedora Silverblue.
+    async fn get_staged(&self) -> Result<Vec<NVR>, Error> {
+        Ok(Vec::new())
+    }
+
+    /// This method returns the `%changelog` entries of an installed binary package, newest first.
+    async fn get_changelog(&self, _nvra: &str) -> Result<Vec<ChangelogEntry>, Error> {
+        Ok(Vec::new())
+    }
+
+    /// This method returns the versions (as `VERSION-RELEASE` strings) of binary packages that new
+    /// changelog entries should be determined against, for example, versions that were installed
+    /// previously, or versions that are available from stable repositories.
+    async fn get_baseline_versions(&self, _names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(HashMap::new())
+    }
+
+    /// This method returns information about the running system that can be determined without
+    /// inspecting running processes, i.e. the boot time, the running kernel, and the hardware
+    /// model.
+    async fn get_system_details(&self) -> Result<RunningSystem, Error> {
+        Ok(RunningSystem::default())
+    }
+
+    /// This method returns information about the running system, i.e. the boot time, the running
+    /// kernel, deleted files that are still in use by running processes, and the hardware model.
+    async fn get_running_system(&self) -> Result<RunningSystem, Error> {
+        Ok(RunningSystem::default())
+    }
+
+    /// This method returns a map from installed binary packages to the files they contain.
+    async fn get_files(&self, _nvras: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        Ok(HashMap::new())
+    }
+}
+
+/// This struct contains all information about installed packages that was collected from an
+/// [`Inventory`].
+#[derive(Debug)]
+pub struct InstalledPackages {
+    /// Fedora release of the current system
+    pub release: FedoraRelease,
+    /// source package NVRs of installed packages
+    pub packages: Vec<NVR>,
+    /// map from source -> binary package NVRs for installed packages
+    pub src_bin_map: HashMap<String, Vec<String>>,
+    /// map from package names to the `Summary` header of installed packages
+    pub summaries: HashMap<String, String>,
+    /// map from binary packages to their installation times
+    pub install_times: HashMap<String, DateTime<Utc>>,
+    /// source package NVRs of packages that are only present in a staged deployment
+    pub staged: Vec<NVR>,
+}
+
+impl InstalledPackages {
+    /// This method collects all information about installed packages from an [`Inventory`].
+    pub async fn collect(inventory: &impl Inventory) -> Result<InstalledPackages, Error> {
+        // independent queries run concurrently
+        let (release, packages, src_bin_map, summaries, install_times, staged) = tokio::try_join!(
+            inventory.get_release(),
+            inventory.get_installed(),
+            inventory.get_src_bin_map(),
+            inventory.get_summaries(),
+            inventory.get_installation_times(),
+            inventory.get_staged(),
+        )?;
+
+        Ok(InstalledPackages {
+            release,
+            packages,
+            src_bin_map,
+            summaries,
+            install_times,
+            staged,
+        })
+    }
+}
+
+/// This enum wraps all available [`Inventory`] implementations, so the one that is used can be
+/// selected at runtime.
+#[derive(Debug)]
+pub enum SystemInventory {
+    /// query `dnf` and `rpm` on the current system
+    Dnf(DnfInventory),
+    /// query `rpm-ostree` and the RPM databases of ostree deployments on the current system
+    Ostree(OstreeInventory),
+    /// read canned data from a file
+    Fixture(Box<FixtureInventory>),
+}
+
+impl SystemInventory {
+    /// This method selects the appropriate [`Inventory`] implementation for the current system:
+    /// `rpm-ostree` based systems (like Fedora Silverblue) use [`OstreeInventory`], and all other
+    /// systems use [`DnfInventory`].
+    pub async fn detect() -> Result<SystemInventory, Error> {
+        if Path::new(OSTREE_BOOTED).exists() {
+            Ok(SystemInventory::Ostree(OstreeInventory::detect().await?))
+        } else {
+            Ok(SystemInventory::Dnf(DnfInventory::detect().await?))
+        }
+    }
+
+    /// This method returns warnings about problems that were encountered while detecting the
+    /// installed packages, which the caller is responsible for reporting.
+    pub fn warnings(&self) -> &[String] {
+        match self {
+            SystemInventory::Ostree(inventory) => inventory.warnings(),
+            SystemInventory::Dnf(_) | SystemInventory::Fixture(_) => &[],
+        }
+    }
+}
+
+impl Inventory for SystemInventory {
+    async fn get_release(&self) -> Result<FedoraRelease, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_release().await,
+            SystemInventory::Ostree(inventory) => inventory.get_release().await,
+            SystemInventory::Fixture(inventory) => inventory.get_release().await,
+        }
+    }
+
+    async fn is_update_testing_enabled(&self) -> Result<bool, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.is_update_testing_enabled().await,
+            SystemInventory::Ostree(inventory) => inventory.is_update_testing_enabled().await,
+            SystemInventory::Fixture(inventory) => inventory.is_update_testing_enabled().await,
+        }
+    }
+
+    async fn get_installed(&self) -> Result<Vec<NVR>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_installed().await,
+            SystemInventory::Ostree(inventory) => inventory.get_installed().await,
+            SystemInventory::Fixture(inventory) => inventory.get_installed().await,
+        }
+    }
+
+    async fn get_src_bin_map(&self) -> Result<HashMap<String, Vec<String>>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_src_bin_map().await,
+            SystemInventory::Ostree(inventory) => inventory.get_src_bin_map().await,
+            SystemInventory::Fixture(inventory) => inventory.get_src_bin_map().await,
+        }
+    }
+
+    async fn get_summaries(&self) -> Result<HashMap<String, String>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_summaries().await,
+            SystemInventory::Ostree(inventory) => inventory.get_summaries().await,
+            SystemInventory::Fixture(inventory) => inventory.get_summaries().await,
+        }
+    }
+
+    async fn get_installation_times(&self) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_installation_times().await,
+            SystemInventory::Ostree(inventory) => inventory.get_installation_times().await,
+            SystemInventory::Fixture(inventory) => inventory.get_installation_times().await,
+        }
+    }
+
+    async fn get_staged(&self) -> Result<Vec<NVR>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_staged().await,
+            SystemInventory::Ostree(inventory) => inventory.get_staged().await,
+            SystemInventory::Fixture(inventory) => inventory.get_staged().await,
+        }
+    }
+
+    async fn get_changelog(&self, nvra: &str) -> Result<Vec<ChangelogEntry>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_changelog(nvra).await,
+            SystemInventory::Ostree(inventory) => inventory.get_changelog(nvra).await,
+            SystemInventory::Fixture(inventory) => inventory.get_changelog(nvra).await,
+        }
+    }
+
+    async fn get_baseline_versions(&self, names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_baseline_versions(names).await,
+            SystemInventory::Ostree(inventory) => inventory.get_baseline_versions(names).await,
+            SystemInventory::Fixture(inventory) => inventory.get_baseline_versions(names).await,
+        }
+    }
+
+    async fn get_system_details(&self) -> Result<RunningSystem, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_system_details().await,
+            SystemInventory::Ostree(inventory) => inventory.get_system_details().await,
+            SystemInventory::Fixture(inventory) => inventory.get_system_details().await,
+        }
+    }
+
+    async fn get_running_system(&self) -> Result<RunningSystem, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_running_system().await,
+            SystemInventory::Ostree(inventory) => inventory.get_running_system().await,
+            SystemInventory::Fixture(inventory) => inventory.get_running_system().await,
+        }
+    }
+
+    async fn get_files(&self, nvras: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        match self {
+            SystemInventory::Dnf(inventory) => inventory.get_files(nvras).await,
+            SystemInventory::Ostree(inventory) => inventory.get_files(nvras).await,
+            SystemInventory::Fixture(inventory) => inventory.get_files(nvras).await,
+        }
+    }
+}
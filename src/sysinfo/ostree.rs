@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bodhi::FedoraRelease;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::proc::{query_running_system, query_system_details};
+use super::rpm::{
+    installation_times,
+    installed_sources,
+    query_changelog,
+    query_files,
+    query_rpmdb,
+    src_bin_map,
+    summaries,
+    versions_by_name,
+    RpmPackage,
+};
+use super::{query_release, run_command, Inventory, RunningSystem};
+use crate::changelog::ChangelogEntry;
+use crate::error::Error;
+use crate::nvr::NVR;
+
+// directory that contains the configuration of enabled package repositories
+const REPOS_DIR: &str = "/etc/yum.repos.d";
+
+/// This struct represents the parts of the output of `rpm-ostree status --json` that are used.
+#[derive(Debug, Deserialize)]
+struct OstreeStatus {
+    deployments: Vec<Deployment>,
+}
+
+/// This struct represents one ostree deployment as listed by `rpm-ostree status --json`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Deployment {
+    osname: String,
+    checksum: String,
+    serial: u32,
+    booted: bool,
+    #[serde(default)]
+    staged: bool,
+}
+
+impl Deployment {
+    /// This method returns the location of the RPM database of this deployment. It contains both
+    /// packages from the base image and layered packages.
+    fn rpmdb_path(&self) -> PathBuf {
+        PathBuf::from(format!(
+            "/ostree/deploy/{}/deploy/{}.{}/usr/share/rpm",
+            self.osname, self.checksum, self.serial
+        ))
+    }
+}
+
+/// This helper function parses the output of `rpm-ostree status --json`.
+fn parse_status(output: &str) -> Result<OstreeStatus, Error> {
+    serde_json::from_str(output).map_err(|error| Error::parse_with("Failed to parse rpm-ostree output", error))
+}
+
+/// This helper function returns the booted deployment and the deployment that will be booted next
+/// (if there is one).
+///
+/// New deployments (either staged or finalized) are always listed before the currently booted
+/// deployment, and rollback deployments are always listed after it.
+fn select_deployments(status: &OstreeStatus) -> Result<(&Deployment, Option<&Deployment>), Error> {
+    let booted = status
+        .deployments
+        .iter()
+        .position(|deployment| deployment.booted)
+        .ok_or_else(|| Error::parse("Failed to determine the booted ostree deployment."))?;
+
+    let pending = status
+        .deployments
+        .iter()
+        .find(|deployment| deployment.staged)
+        .or_else(|| status.deployments[..booted].first());
+
+    Ok((&status.deployments[booted], pending))
+}
+
+/// This helper function returns the rollback deployment, i.e. the deployment that was booted before
+/// the currently booted one (if there is one).
+fn select_rollback(status: &OstreeStatus) -> Option<&Deployment> {
+    let booted = status.deployments.iter().position(|deployment| deployment.booted)?;
+    status.deployments.get(booted + 1)
+}
+
+/// This helper function determines whether the "updates-testing" repository is enabled in the
+/// contents of a `.repo` file.
+fn parse_repo_file(contents: &str) -> bool {
+    let mut section = "";
+    let mut enabled = false;
+
+    for line in contents.lines().map(|line| line.trim()) {
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line.trim_start_matches('[').trim_end_matches(']').trim();
+            continue;
+        }
+
+        if section != "updates-testing" {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim() == "enabled" {
+                enabled = matches!(value.trim(), "1" | "true" | "True" | "yes");
+            }
+        }
+    }
+
+    enabled
+}
+
+/// This struct provides information about installed packages on `rpm-ostree` based systems (like
+/// Fedora Silverblue or Kinoite), where `dnf repoquery --installed` is not usable.
+///
+/// Packages are read from the RPM database of the booted deployment. Packages that are only
+/// present in a staged deployment are tracked separately, since they are not actually in use until
+/// the system has been rebooted.
+#[derive(Debug)]
+pub struct OstreeInventory {
+    booted: Vec<RpmPackage>,
+    staged: Vec<NVR>,
+    rollback: Option<PathBuf>,
+    warnings: Vec<String>,
+}
+
+impl OstreeInventory {
+    /// This method queries `rpm-ostree` for deployments on the current system and reads the RPM
+    /// databases of the booted and staged deployments.
+    pub async fn detect() -> Result<OstreeInventory, Error> {
+        let output = run_command(
+            Command::new("rpm-ostree").arg("status").arg("--json"),
+            "rpm-ostree status",
+        )
+        .await?;
+
+        let status = parse_status(&output)?;
+        let (_, pending) = select_deployments(&status)?;
+
+        // the RPM database of the booted deployment is available at the default location, and it
+        // contains both packages from the base image and layered packages
+        let packages = query_rpmdb(None).await?;
+        let installed = installed_sources(&packages)?;
+
+        let mut warnings = Vec::new();
+
+        let staged = match pending {
+            Some(deployment) => match query_rpmdb(Some(&deployment.rpmdb_path())).await {
+                Ok(staged) => installed_sources(&staged)?
+                    .into_iter()
+                    .filter(|nvr| !installed.contains(nvr))
+                    .collect(),
+                Err(error) => {
+                    warnings.push(format!(
+                        "Failed to read packages in the staged deployment: {}",
+                        error.report()
+                    ));
+                    Vec::new()
+                },
+            },
+            None => Vec::new(),
+        };
+
+        Ok(OstreeInventory {
+            booted: packages,
+            staged,
+            rollback: select_rollback(&status).map(Deployment::rpmdb_path),
+            warnings,
+        })
+    }
+
+    /// This method returns warnings about problems that were encountered while reading the
+    /// deployments, but which did not prevent reading the installed packages.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+impl Inventory for OstreeInventory {
+    async fn get_release(&self) -> Result<FedoraRelease, Error> {
+        query_release().await
+    }
+
+    /// This method checks the repository configuration in `/etc/yum.repos.d` for whether the
+    /// "updates-testing" repository is enabled.
+    async fn is_update_testing_enabled(&self) -> Result<bool, Error> {
+        let mut entries = tokio::fs::read_dir(REPOS_DIR)
+            .await
+            .map_err(|error| Error::io(format!("Failed to read {}", REPOS_DIR), error))?;
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|error| Error::io(format!("Failed to read {}", REPOS_DIR), error))?
+        {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("repo") {
+                continue;
+            }
+
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|error| Error::io(format!("Failed to read {}", path.display()), error))?;
+
+            if parse_repo_file(&contents) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    async fn get_installed(&self) -> Result<Vec<NVR>, Error> {
+        installed_sources(&self.booted)
+    }
+
+    async fn get_src_bin_map(&self) -> Result<HashMap<String, Vec<String>>, Error> {
+        src_bin_map(&self.booted)
+    }
+
+    async fn get_summaries(&self) -> Result<HashMap<String, String>, Error> {
+        Ok(summaries(&self.booted))
+    }
+
+    async fn get_installation_times(&self) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+        Ok(installation_times(&self.booted))
+    }
+
+    async fn get_staged(&self) -> Result<Vec<NVR>, Error> {
+        Ok(self.staged.clone())
+    }
+
+    /// This method queries the RPM database of the booted deployment for the `%changelog` of an
+    /// installed package.
+    async fn get_changelog(&self, nvra: &str) -> Result<Vec<ChangelogEntry>, Error> {
+        query_changelog(nvra).await
+    }
+
+    /// This method returns the versions of packages in the rollback deployment, i.e. the versions
+    /// that were installed before the last upgrade.
+    async fn get_baseline_versions(&self, names: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        match &self.rollback {
+            Some(path) => Ok(versions_by_name(&query_rpmdb(Some(path)).await?, names)),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// This method reads the running kernel and the hardware model from `/proc` and `/sys`.
+    async fn get_system_details(&self) -> Result<RunningSystem, Error> {
+        query_system_details().await
+    }
+
+    /// This method reads information about the running system from `/proc`.
+    async fn get_running_system(&self) -> Result<RunningSystem, Error> {
+        query_running_system().await
+    }
+
+    /// This method queries the RPM database for the files in installed packages.
+    async fn get_files(&self, nvras: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+        query_files(nvras).await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deployments() {
+        let status = parse_status(include_str!("../../tests/fixtures/rpm-ostree/status.json")).unwrap();
+        let (booted, pending) = select_deployments(&status).unwrap();
+
+        assert_eq!(booted.serial, 0);
+
+        let pending = pending.unwrap();
+        assert!(pending.staged);
+        assert_eq!(
+            pending.rpmdb_path(),
+            PathBuf::from("/ostree/deploy/fedora/deploy/9f5a0e8c2d7b.1/usr/share/rpm")
+        );
+
+        let status = parse_status(include_str!("../../tests/fixtures/rpm-ostree/status-booted.json")).unwrap();
+        let (booted, pending) = select_deployments(&status).unwrap();
+
+        assert_eq!(booted.checksum, "3c1b7d4e6a90");
+        // rollback deployments are not pending
+        assert!(pending.is_none());
+        assert!(select_rollback(&status).is_some());
+    }
+
+    #[test]
+    fn repo_file() {
+        let repo = include_str!("../../tests/fixtures/rpm-ostree/fedora-updates-testing.repo");
+        assert!(parse_repo_file(repo));
+        assert!(!parse_repo_file(&repo.replace("enabled=1", "enabled=0")));
+        assert!(!parse_repo_file("[updates]\nenabled=1\n"));
+    }
+}
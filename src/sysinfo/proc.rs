@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use tokio::fs::{read_dir, read_to_string};
+
+use super::RunningSystem;
+use crate::error::Error;
+
+// file that contains kernel and system statistics, including the boot time
+const PROC_STAT: &str = "/proc/stat";
+
+// file that contains the release of the running kernel (i.e. the output of `uname -r`)
+const PROC_OSRELEASE: &str = "/proc/sys/kernel/osrelease";
+
+// files that contain the vendor and model of the hardware
+const DMI_VENDOR: &str = "/sys/class/dmi/id/sys_vendor";
+const DMI_PRODUCT: &str = "/sys/class/dmi/id/product_name";
+
+// placeholder values that some firmware reports instead of the actual vendor or model
+const DMI_PLACEHOLDERS: &[&str] = &["To Be Filled By O.E.M.", "System Product Name", "Default string"];
+
+// only deleted files in these directories are considered (i.e. libraries and programs that were
+// installed from packages, but not temporary files or shared memory)
+const PACKAGED_DIRS: &[&str] = &["/usr/", "/lib/", "/lib64/", "/opt/"];
+
+/// This helper function parses the boot time from the contents of `/proc/stat`.
+pub fn parse_boot_time(stat: &str) -> Option<DateTime<Utc>> {
+    let line = stat.lines().find(|line| line.starts_with("btime "))?;
+    let timestamp: i64 = line.trim_start_matches("btime ").trim().parse().ok()?;
+    DateTime::from_timestamp(timestamp, 0)
+}
+
+/// This helper function returns the paths of packaged files that were deleted, but are still
+/// mapped into memory, from the contents of a `/proc/PID/maps` file.
+pub fn parse_deleted_maps(maps: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+
+    for line in maps.lines() {
+        let Some(line) = line.strip_suffix(" (deleted)") else {
+            continue;
+        };
+
+        // the path is the last column, and it is the only column that can contain a slash
+        let Some(index) = line.find('/') else {
+            continue;
+        };
+        let path = &line[index..];
+
+        if PACKAGED_DIRS.iter().any(|dir| path.starts_with(dir)) && !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    }
+
+    paths
+}
+
+/// This helper function returns a description of the hardware from the vendor and model values
+/// that are reported by the firmware. Placeholder values are ignored.
+pub fn describe_hardware(vendor: &str, product: &str) -> Option<String> {
+    let parts: Vec<&str> = [vendor.trim(), product.trim()]
+        .into_iter()
+        .filter(|part| !part.is_empty() && !DMI_PLACEHOLDERS.contains(part))
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" "))
+    }
+}
+
+/// This helper function reads the boot time and the release of the running kernel from `/proc`, and
+/// the hardware model from `/sys`, without inspecting running processes.
+pub async fn query_system_details() -> Result<RunningSystem, Error> {
+    let stat = read_to_string(PROC_STAT)
+        .await
+        .map_err(|error| Error::io(format!("Unable to read {}", PROC_STAT), error))?;
+
+    let boot_time = parse_boot_time(&stat);
+    let kernel = read_to_string(PROC_OSRELEASE)
+        .await
+        .ok()
+        .map(|release| release.trim().to_string());
+
+    let vendor = read_to_string(DMI_VENDOR).await.unwrap_or_default();
+    let product = read_to_string(DMI_PRODUCT).await.unwrap_or_default();
+    let hardware = describe_hardware(&vendor, &product);
+
+    Ok(RunningSystem {
+        boot_time,
+        kernel,
+        deleted_files: HashMap::new(),
+        hardware,
+    })
+}
+
+/// This helper function reads information about the running system from `/proc` (and the hardware
+/// model from `/sys`), including deleted files that are still in use by running processes.
+///
+/// Processes that cannot be inspected (for example, processes of other users when not running as
+/// root) are skipped.
+pub async fn query_running_system() -> Result<RunningSystem, Error> {
+    let mut running = query_system_details().await?;
+
+    let mut deleted_files: HashMap<String, Vec<String>> = HashMap::new();
+
+    let mut entries = read_dir("/proc")
+        .await
+        .map_err(|error| Error::io("Unable to list running processes", error))?;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let pid = entry.file_name().to_string_lossy().to_string();
+        if !pid.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        let dir = Path::new("/proc").join(&pid);
+        let Ok(maps) = read_to_string(dir.join("maps")).await else {
+            continue;
+        };
+        let name = read_to_string(dir.join("comm")).await.unwrap_or_default();
+
+        for path in parse_deleted_maps(&maps) {
+            deleted_files
+                .entry(path)
+                .or_default()
+                .push(format!("{} ({})", name.trim(), pid));
+        }
+    }
+
+    running.deleted_files = deleted_files;
+    Ok(running)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = include_str!("../../tests/fixtures/proc/maps.txt");
+
+    #[test]
+    fn boot_time() {
+        let stat = "cpu  1 2 3 4\nintr 12345\nctxt 67890\nbtime 1714644000\nprocesses 4321\n";
+        assert_eq!(parse_boot_time(stat).unwrap().to_rfc3339(), "2024-05-02T10:00:00+00:00");
+        assert_eq!(parse_boot_time("cpu  1 2 3 4\n"), None);
+    }
+
+    #[test]
+    fn hardware() {
+        assert_eq!(
+            describe_hardware("LENOVO\n", "21AH00BSGE\n").as_deref(),
+            Some("LENOVO 21AH00BSGE")
+        );
+        assert_eq!(
+            describe_hardware("ASUS\n", "System Product Name\n").as_deref(),
+            Some("ASUS")
+        );
+        assert_eq!(describe_hardware("", "To Be Filled By O.E.M."), None);
+    }
+
+    #[test]
+    fn deleted_maps() {
+        assert_eq!(
+            parse_deleted_maps(MAPS),
+            vec!["/usr/lib64/libfoo.so.1.1.0", "/usr/lib64/libfoo with spaces.so"]
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use tokio::process::Command;
+
+use super::run_command;
+use crate::changelog::{parse_changelog, ChangelogEntry};
+use crate::error::Error;
+use crate::nvr::NVR;
+use crate::parse::parse_filename;
+
+/// Query format for reading all information that is needed about installed packages in one pass.
+/// It is understood by both `rpm` and `dnf repoquery`. The summary is printed last, since it is the
+/// only value that could contain whitespace.
+pub const QUERY_FORMAT: &str = "%{name}\t%{version}\t%{release}\t%{arch}\t%{sourcerpm}\t%{installtime}\t%{summary}";
+
+/// This struct contains information about an installed binary package.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RpmPackage {
+    /// name of the binary package
+    pub name: String,
+    /// version of the package
+    pub version: String,
+    /// release of the package
+    pub release: String,
+    /// architecture of the package
+    pub arch: String,
+    /// file name of the source package (if any)
+    pub sourcerpm: Option<String>,
+    /// date & time when the package was installed
+    pub installtime: DateTime<Utc>,
+    /// summary of the package
+    pub summary: String,
+}
+
+impl RpmPackage {
+    /// This method returns the NVRA of this binary package.
+    pub fn nvra(&self) -> String {
+        format!("{}-{}-{}.{}", self.name, self.version, self.release, self.arch)
+    }
+
+    /// This method returns the NVR of the source package this binary package was built from.
+    pub fn source_nvr(&self) -> Result<Option<NVR>, Error> {
+        match &self.sourcerpm {
+            Some(sourcerpm) => {
+                let (n, _, v, r, _) = parse_filename(sourcerpm)?;
+                Ok(Some(NVR {
+                    n: n.to_string(),
+                    v: v.to_string(),
+                    r: r.to_string(),
+                }))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// This helper function parses an installation time as printed by `rpm` or `dnf`.
+///
+/// `rpm` and dnf5 print UNIX timestamps, while dnf4 prints formatted dates (`2024-05-02 10:00`).
+/// Some versions of dnf5 print formatted dates including seconds.
+pub fn parse_install_time(string: &str) -> Result<DateTime<Utc>, Error> {
+    let string = string.trim();
+
+    if let Ok(timestamp) = string.parse::<i64>() {
+        return DateTime::from_timestamp(timestamp, 0)
+            .ok_or_else(|| Error::parse(format!("Invalid timestamp: {}", string)));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(string, format) {
+            return Ok(datetime.and_utc());
+        }
+    }
+
+    Err(Error::parse(format!("Failed to parse installation time: {}", string)))
+}
+
+/// This helper function parses a list of installed packages that was printed with
+/// [`QUERY_FORMAT`].
+pub fn parse_packages(output: &str) -> Result<Vec<RpmPackage>, Error> {
+    let mut packages: Vec<RpmPackage> = Vec::new();
+
+    for line in output.lines().filter(|line| !line.trim().is_empty()) {
+        let parts: Vec<&str> = line.splitn(7, '\t').collect();
+
+        let (name, version, release, arch, sourcerpm, installtime, summary) = match parts.as_slice() {
+            [name, version, release, arch, sourcerpm, installtime, summary] => {
+                (name, version, release, arch, sourcerpm, installtime, summary)
+            },
+            _ => return Err(Error::parse(format!("Failed to parse package list: {}", line))),
+        };
+
+        let installtime = parse_install_time(installtime)
+            .map_err(|error| Error::parse_with(format!("Failed to parse package list: {}", line), error))?;
+
+        // packages without source package (for example, gpg-pubkey) have no value for SOURCERPM
+        let sourcerpm = match sourcerpm.trim() {
+            "(none)" | "None" | "" => None,
+            value => Some(value.to_string()),
+        };
+
+        packages.push(RpmPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            release: release.to_string(),
+            arch: arch.to_string(),
+            sourcerpm,
+            installtime,
+            summary: summary.to_string(),
+        });
+    }
+
+    Ok(packages)
+}
+
+/// This helper function queries the RPM database for all installed packages. If `dbpath` is
+/// specified, the RPM database at this location is queried instead of the default one.
+pub async fn query_rpmdb(dbpath: Option<&Path>) -> Result<Vec<RpmPackage>, Error> {
+    let mut command = Command::new("rpm");
+
+    if let Some(dbpath) = dbpath {
+        command.arg("--dbpath").arg(dbpath);
+    }
+
+    command
+        .arg("--query")
+        .arg("--all")
+        .arg("--queryformat")
+        .arg(format!("{}\n", QUERY_FORMAT));
+
+    let output = run_command(&mut command, "rpm --query").await?;
+    parse_packages(&output)
+}
+
+/// This helper function returns the source package NVRs of installed packages (without duplicates).
+pub fn installed_sources(packages: &[RpmPackage]) -> Result<Vec<NVR>, Error> {
+    let sources: BTreeSet<&str> = packages.iter().filter_map(|p| p.sourcerpm.as_deref()).collect();
+
+    let mut nvrs: Vec<NVR> = Vec::new();
+    for source in sources {
+        let (n, _, v, r, _) = parse_filename(source)?;
+        nvrs.push(NVR {
+            n: n.to_string(),
+            v: v.to_string(),
+            r: r.to_string(),
+        });
+    }
+
+    Ok(nvrs)
+}
+
+/// This helper function queries the RPM database for the `%changelog` of an installed package.
+pub async fn query_changelog(nvra: &str) -> Result<Vec<ChangelogEntry>, Error> {
+    let output = run_command(
+        Command::new("rpm").arg("--query").arg("--changelog").arg(nvra),
+        "rpm --query --changelog",
+    )
+    .await?;
+
+    Ok(parse_changelog(&output))
+}
+
+/// This helper function parses a list of files in installed packages that was printed with the
+/// `[%{=NAME}-%{=VERSION}-%{=RELEASE}.%{=ARCH}\t%{FILENAMES}\n]` query format.
+pub fn parse_files(output: &str) -> Result<HashMap<String, Vec<String>>, Error> {
+    let mut files: HashMap<String, Vec<String>> = HashMap::new();
+
+    for line in output.lines().filter(|line| !line.trim().is_empty()) {
+        let (nvra, path) = line
+            .split_once('\t')
+            .ok_or_else(|| Error::parse(format!("Failed to parse file list: {}", line)))?;
+
+        // packages without files have one "(none)" entry
+        if path.starts_with('/') {
+            files.entry(nvra.to_string()).or_default().push(path.to_string());
+        }
+    }
+
+    Ok(files)
+}
+
+/// This helper function queries the RPM database for the files in installed packages.
+pub async fn query_files(nvras: &[String]) -> Result<HashMap<String, Vec<String>>, Error> {
+    if nvras.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let output = run_command(
+        Command::new("rpm")
+            .arg("--query")
+            .arg("--queryformat")
+            .arg("[%{=NAME}-%{=VERSION}-%{=RELEASE}.%{=ARCH}\t%{FILENAMES}\n]")
+            .args(nvras),
+        "rpm --query",
+    )
+    .await?;
+
+    parse_files(&output)
+}
+
+/// This helper function returns a map from package names to the `VERSION-RELEASE` strings of the
+/// packages with these names.
+pub fn versions_by_name(packages: &[RpmPackage], names: &[String]) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+
+    for package in packages.iter().filter(|package| names.contains(&package.name)) {
+        let version = format!("{}-{}", package.version, package.release);
+        let entry = versions.entry(package.name.clone()).or_default();
+        if !entry.contains(&version) {
+            entry.push(version);
+        }
+    }
+
+    versions
+}
+
+/// This helper function returns a map from source -> binary package NVRs for installed packages.
+pub fn src_bin_map(packages: &[RpmPackage]) -> Result<HashMap<String, Vec<String>>, Error> {
+    let mut pkg_map: HashMap<String, Vec<String>> = HashMap::new();
+
+    for package in packages {
+        if let Some(source) = package.source_nvr()? {
+            pkg_map.entry(source.to_string()).or_default().push(package.nvra());
+        }
+    }
+
+    Ok(pkg_map)
+}
+
+/// This helper function returns a map from package names to the `Summary` header of installed
+/// packages.
+pub fn summaries(packages: &[RpmPackage]) -> HashMap<String, String> {
+    packages
+        .iter()
+        .map(|package| (package.name.clone(), package.summary.clone()))
+        .collect()
+}
+
+/// This helper function returns a map from binary packages to their installation times.
+pub fn installation_times(packages: &[RpmPackage]) -> HashMap<String, DateTime<Utc>> {
+    packages
+        .iter()
+        .map(|package| (package.nvra(), package.installtime))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = include_str!("../../tests/fixtures/rpm/query.txt");
+
+    #[test]
+    fn parse() {
+        let packages = parse_packages(OUTPUT).unwrap();
+        assert_eq!(packages.len(), 6);
+
+        let bash = &packages[0];
+        assert_eq!(bash.nvra(), "bash-5.2.32-1.fc41.x86_64");
+        assert_eq!(bash.sourcerpm.as_deref(), Some("bash-5.2.32-1.fc41.src.rpm"));
+        assert_eq!(bash.installtime.to_rfc3339(), "2024-10-22T08:00:00+00:00");
+        assert_eq!(bash.summary, "The GNU Bourne Again shell");
+
+        // summaries can contain tab characters
+        assert_eq!(packages[4].summary, "Weird\tsummary");
+        // packages without source package
+        assert_eq!(packages[5].sourcerpm, None);
+    }
+
+    #[test]
+    fn maps() {
+        let packages = parse_packages(OUTPUT).unwrap();
+
+        let sources = installed_sources(&packages).unwrap();
+        assert_eq!(sources.len(), 4);
+        assert!(sources.contains(&"glibc-2.40-9.fc41".parse().unwrap()));
+
+        let map = src_bin_map(&packages).unwrap();
+        assert_eq!(
+            map.get("glibc-2.40-9.fc41").unwrap(),
+            &vec!["glibc-2.40-9.fc41.x86_64", "glibc-common-2.40-9.fc41.x86_64"]
+        );
+
+        assert_eq!(summaries(&packages).get("glibc").unwrap(), "The GNU libc libraries");
+        assert!(installation_times(&packages).contains_key("gpg-pubkey-e99d6ad1-64d2612c.(none)"));
+
+        let versions = versions_by_name(&packages, &[String::from("glibc"), String::from("missing")]);
+        assert_eq!(versions.get("glibc").unwrap(), &vec!["2.40-9.fc41"]);
+        assert!(!versions.contains_key("missing"));
+    }
+
+    #[test]
+    fn files() {
+        let output = "glibc-2.40-9.fc41.x86_64\t/usr/lib64/libc.so.6\n\
+                      glibc-2.40-9.fc41.x86_64\t/usr/lib64/libm.so.6\n\
+                      empty-1.0-1.fc41.noarch\t(none)\n";
+
+        let files = parse_files(output).unwrap();
+        assert_eq!(
+            files.get("glibc-2.40-9.fc41.x86_64").unwrap(),
+            &vec!["/usr/lib64/libc.so.6", "/usr/lib64/libm.so.6"]
+        );
+        assert!(!files.contains_key("empty-1.0-1.fc41.noarch"));
+        assert!(parse_files("garbage\n").is_err());
+    }
+
+    #[test]
+    fn install_time() {
+        assert_eq!(
+            parse_install_time("2024-10-22 08:00:00").unwrap(),
+            parse_install_time("1729584000").unwrap()
+        );
+        assert_eq!(
+            parse_install_time("2024-10-22 08:00").unwrap(),
+            parse_install_time("1729584000").unwrap()
+        );
+        assert!(parse_install_time("Tue Oct 22 08:00:00 2024").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use bodhi::Karma;
+use chrono::Duration;
+use tokio::fs::read_to_string;
+
+use crate::config::CommentTemplate;
+use crate::output::proper_plural;
+
+// file that contains the host name of the current system
+const PROC_HOSTNAME: &str = "/proc/sys/kernel/hostname";
+
+/// This struct contains the values for placeholders in comment templates.
+#[derive(Debug)]
+pub struct TemplateContext {
+    /// update alias (`{alias}`)
+    pub alias: String,
+    /// installed builds from the update (`{builds}`)
+    pub builds: Vec<String>,
+    /// Fedora release of the current system, for example, "F40" (`{release}`)
+    pub release: String,
+    /// time since the update was installed, if known (`{installed_for}`)
+    pub installed_for: Option<Duration>,
+    /// host name of the current system (`{hostname}`)
+    pub hostname: String,
+}
+
+/// This struct contains a comment template with all placeholders replaced.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RenderedTemplate {
+    /// name of the template
+    pub name: String,
+    /// text of the comment
+    pub text: String,
+    /// karma that is provided together with the comment, if specified
+    pub karma: Option<Karma>,
+}
+
+/// This helper function returns a compact representation of the time since an update was
+/// installed, with a granularity of days (or hours, for updates that were installed recently).
+fn pretty_age(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) if duration >= Duration::days(1) => proper_plural(duration.num_days(), "day"),
+        Some(duration) if duration >= Duration::hours(1) => proper_plural(duration.num_hours(), "hour"),
+        Some(_) => String::from("less than an hour"),
+        None => String::from("an unknown time"),
+    }
+}
+
+/// This helper function reads the host name of the current system.
+pub async fn hostname() -> String {
+    match read_to_string(PROC_HOSTNAME).await {
+        Ok(hostname) if !hostname.trim().is_empty() => hostname.trim().to_string(),
+        _ => String::from("localhost"),
+    }
+}
+
+/// This function replaces all known placeholders in the text of a comment template. Unknown
+/// placeholders are left as they are.
+pub fn render(text: &str, context: &TemplateContext) -> String {
+    let values = [
+        ("{alias}", context.alias.clone()),
+        ("{builds}", context.builds.join(", ")),
+        ("{release}", context.release.clone()),
+        ("{installed_for}", pretty_age(context.installed_for)),
+        ("{hostname}", context.hostname.clone()),
+    ];
+
+    let mut rendered = text.trim().to_string();
+    for (placeholder, value) in values {
+        rendered = rendered.replace(placeholder, &value);
+    }
+
+    rendered
+}
+
+/// This function renders all configured comment templates for an update.
+pub fn render_templates(
+    templates: &BTreeMap<String, CommentTemplate>,
+    context: &TemplateContext,
+) -> Vec<RenderedTemplate> {
+    templates
+        .iter()
+        .map(|(name, template)| RenderedTemplate {
+            name: name.clone(),
+            text: render(&template.text, context),
+            karma: template.karma,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            alias: String::from("FEDORA-2024-0001aaaa"),
+            builds: vec![String::from("foo-1.2-1.fc40"), String::from("bar-2.0-1.fc40")],
+            release: String::from("F40"),
+            installed_for: Some(Duration::days(7) + Duration::hours(3)),
+            hostname: String::from("workstation"),
+        }
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            render(
+                "Works fine on my {release} {hostname}, used daily for {installed_for}.\n",
+                &context()
+            ),
+            "Works fine on my F40 workstation, used daily for 7 days."
+        );
+        assert_eq!(
+            render("Tested {builds} from {alias} with {unknown}.", &context()),
+            "Tested foo-1.2-1.fc40, bar-2.0-1.fc40 from FEDORA-2024-0001aaaa with {unknown}."
+        );
+    }
+
+    #[test]
+    fn templates() {
+        let config: BTreeMap<String, CommentTemplate> = toml::from_str(
+            "[smoke-tested]\ntext = \"Smoke tested on {release}.\"\n\n\
+             [works-for-me]\ntext = \"Works for me.\"\nkarma = 1\n",
+        )
+        .unwrap();
+
+        let rendered = render_templates(&config, &context());
+        assert_eq!(
+            rendered,
+            vec![
+                RenderedTemplate {
+                    name: String::from("smoke-tested"),
+                    text: String::from("Smoke tested on F40."),
+                    karma: None,
+                },
+                RenderedTemplate {
+                    name: String::from("works-for-me"),
+                    text: String::from("Works for me."),
+                    karma: Some(Karma::Positive),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(pretty_age(Some(Duration::days(1))), "1 day");
+        assert_eq!(pretty_age(Some(Duration::hours(5))), "5 hours");
+        assert_eq!(pretty_age(Some(Duration::minutes(5))), "less than an hour");
+        assert_eq!(pretty_age(None), "an unknown time");
+    }
+}
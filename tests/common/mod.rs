@@ -0,0 +1,337 @@
+//! This module contains a test harness that runs the `fedora-update-feedback` binary against a
+//! local stand-in for a bodhi server (which serves recorded JSON responses), with canned inventory
+//! data, and with a temporary `$HOME` directory.
+
+#![allow(dead_code)]
+#![allow(clippy::unwrap_used)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_fedora-update-feedback");
+
+pub const USERNAME: &str = "tester";
+pub const PASSWORD: &str = "hunter2";
+
+/// This function reads a file from the `tests/fixtures` directory.
+pub fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|error| panic!("Failed to read {}: {}", path.display(), error))
+}
+
+/// This function reads and parses a JSON file from the `tests/fixtures` directory.
+pub fn json_fixture(name: &str) -> Value {
+    serde_json::from_str(&fixture(name)).unwrap()
+}
+
+/// This function wraps a list of updates in the format of a single-page bodhi query result.
+fn update_page(updates: Value) -> Value {
+    let total = updates.as_array().map(|updates| updates.len()).unwrap_or_default();
+
+    json!({
+        "updates": updates,
+        "page": 1,
+        "pages": 1,
+        "rows_per_page": 50,
+        "total": total,
+    })
+}
+
+/// This struct contains the stand-in bodhi server and the temporary home directory that are used
+/// by a single test.
+pub struct StandIn {
+    pub server: MockServer,
+    home: TempDir,
+}
+
+impl StandIn {
+    /// This method starts a stand-in bodhi server that serves the updates from
+    /// `tests/fixtures/updates.json` as "testing" updates.
+    pub async fn start() -> StandIn {
+        StandIn::with_testing(json_fixture("updates.json")).await
+    }
+
+    /// This method starts a stand-in bodhi server that serves the given list of updates as
+    /// "testing" updates.
+    pub async fn with_testing(testing: Value) -> StandIn {
+        let server = MockServer::start().await;
+        let home = TempDir::new().unwrap();
+
+        for (status, updates) in [
+            ("testing", testing),
+            ("pending", json!([])),
+            ("obsolete", json_fixture("bodhi/obsolete.json")),
+            ("unpushed", json_fixture("bodhi/unpushed.json")),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/updates/"))
+                .and(query_param("status", status))
+                .and(query_param("releases", "F40"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(update_page(updates)))
+                .mount(&server)
+                .await;
+        }
+
+        // OpenID login flow: bodhi login page, authentication endpoint, and return URL
+        Mock::given(method("GET"))
+            .and(path("/login"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/openid/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(openid_response(&server.uri())))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/dologin.html"))
+            .respond_with(ResponseTemplate::new(200).insert_header("set-cookie", "auth_tkt=standin; Path=/"))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/csrf"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "csrf_token": "standin" })))
+            .mount(&server)
+            .await;
+
+        let stand_in = StandIn { server, home };
+        stand_in.write_config();
+        stand_in
+    }
+
+    /// This method makes the stand-in server respond to new comments with the given status code
+    /// and JSON body.
+    pub async fn respond_to_comments(&self, status: u16, body: Value) {
+        Mock::given(method("POST"))
+            .and(path("/comments/"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(body))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// This method makes the stand-in server respond to the next new comment with the given status
+    /// code and JSON body (responses for later comments need to be set up separately).
+    pub async fn respond_to_next_comment(&self, status: u16, body: Value) {
+        Mock::given(method("POST"))
+            .and(path("/comments/"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(body))
+            .up_to_n_times(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// This method makes the stand-in server not respond to new comments in time (for example, to
+    /// interrupt the program while it is waiting for the response).
+    pub async fn stall_comments(&self) {
+        Mock::given(method("POST"))
+            .and(path("/comments/"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(60)))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// This method returns the JSON bodies of all comments that were submitted to the stand-in.
+    pub async fn submitted_comments(&self) -> Vec<Value> {
+        self.requests("POST", "/comments/")
+            .await
+            .iter()
+            .map(|request| serde_json::from_slice(&request.body).unwrap())
+            .collect()
+    }
+
+    /// This method returns all requests with the given method and path that were received.
+    pub async fn requests(&self, http_method: &str, url_path: &str) -> Vec<Request> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|request| request.method.as_str() == http_method && request.url.path() == url_path)
+            .collect()
+    }
+
+    fn write_config(&self) {
+        let config = format!(
+            "[FAS]\nusername = \"{}\"\n\n[fedora-update-feedback]\nbodhi-url = \"{}\"\nopenid-url = \"{}/openid/\"\n",
+            USERNAME,
+            self.server.uri(),
+            self.server.uri()
+        );
+
+        for dir in [".config", ".cache", ".local/share"] {
+            std::fs::create_dir_all(self.home.path().join(dir)).unwrap();
+        }
+
+        std::fs::write(self.home.path().join(".config/fedora.toml"), config).unwrap();
+    }
+
+    /// This method appends settings to the `[fedora-update-feedback]` section of the configuration
+    /// file (or adds subsections after it).
+    pub fn append_config(&self, settings: &str) {
+        let path = self.home.path().join(".config/fedora.toml");
+        let config = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, format!("{}{}\n", config, settings)).unwrap();
+    }
+
+    /// This method returns the path of a file relative to the temporary home directory.
+    pub fn home_path(&self, name: &str) -> PathBuf {
+        self.home.path().join(name)
+    }
+
+    /// This method writes a file into the temporary home directory and returns its path.
+    pub fn write_file(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.home_path(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// This method writes a fake editor script that replaces the file it was invoked with with the
+    /// given text, and returns its path.
+    pub fn write_editor(&self, text: &str) -> PathBuf {
+        self.write_editor_script(&format!("printf '{}\\n' > \"$1\"", text))
+    }
+
+    /// This method writes a fake editor that runs the given shell command (with the path of the
+    /// file that is edited as `$1`).
+    pub fn write_editor_script(&self, command: &str) -> PathBuf {
+        let path = self.write_file("editor.sh", &format!("#!/bin/sh\n{}\n", command));
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// This method returns the contents of the local feedback queue.
+    pub fn queue(&self) -> Value {
+        match std::fs::read_to_string(self.home_path(".local/share/fedora-update-feedback.queue")) {
+            Ok(string) => serde_json::from_str(&string).unwrap(),
+            Err(_) => json!({ "entries": [] }),
+        }
+    }
+
+    /// This method writes the contents of the local feedback queue.
+    pub fn set_queue(&self, queue: Value) {
+        self.write_file(".local/share/fedora-update-feedback.queue", &queue.to_string());
+    }
+
+    /// This method returns the contents of the list of ignored updates and packages.
+    pub fn ignored(&self) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(self.home_path(".cache/fedora-update-feedback.ignored")).unwrap())
+            .unwrap()
+    }
+
+    /// This method writes the list of ignored updates and packages.
+    pub fn set_ignored(&self, ignored: Value) {
+        self.write_file(".cache/fedora-update-feedback.ignored", &ignored.to_string());
+    }
+
+    /// This method writes canned inventory data that is used instead of
+    /// `tests/fixtures/inventory.json` for all following runs.
+    pub fn set_inventory(&self, inventory: Value) {
+        self.write_file("inventory.json", &inventory.to_string());
+    }
+
+    /// This method runs the binary with canned inventory data and the given arguments, and writes
+    /// the given input to its standard input.
+    pub async fn run(&self, args: &[&str], input: &str) -> Output {
+        self.run_with_env(args, input, &[]).await
+    }
+
+    /// This method runs the binary like [`StandIn::run`], but with additional environment
+    /// variables.
+    pub async fn run_with_env(&self, args: &[&str], input: &str, env: &[(&str, &Path)]) -> Output {
+        let mut child = self.command(args, env).spawn().unwrap();
+
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(input.as_bytes()).await.unwrap();
+        drop(stdin);
+
+        child.wait_with_output().await.unwrap()
+    }
+
+    /// This method starts the binary like [`StandIn::run`] (with closed standard input), but does
+    /// not wait for it to finish.
+    pub fn spawn(&self, args: &[&str]) -> Child {
+        let mut command = self.command(args, &[]);
+        command.stdin(Stdio::null());
+        command.spawn().unwrap()
+    }
+
+    /// This method prepares running the binary with canned inventory data, the temporary home
+    /// directory, and the given arguments and additional environment variables.
+    fn command(&self, args: &[&str], env: &[(&str, &Path)]) -> Command {
+        let inventory = match self.home_path("inventory.json") {
+            path if path.exists() => path,
+            _ => Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inventory.json"),
+        };
+
+        let mut command = Command::new(BINARY);
+        command
+            .args(args)
+            .env("FUF_INVENTORY_FIXTURE", inventory)
+            .env("HOME", self.home.path())
+            .env("FUF_PASSWORD", PASSWORD)
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_CACHE_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("EDITOR")
+            .env_remove("VISUAL")
+            .env_remove("FUF_LOG")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        for (key, value) in env {
+            command.env(key, value);
+        }
+
+        command
+    }
+}
+
+/// This function returns the standard output of a finished process as a string.
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// This function returns the standard error of a finished process as a string.
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+/// This function returns a successful response from the OpenID authentication endpoint.
+fn openid_response(uri: &str) -> Value {
+    json!({
+        "success": true,
+        "response": {
+            "openid.assoc_handle": "standin",
+            "openid.cla.signed_cla": "http://admin.fedoraproject.org/accounts/cla/done",
+            "openid.claimed_id": format!("http://{}.id.fedoraproject.org/", USERNAME),
+            "openid.identity": format!("http://{}.id.fedoraproject.org/", USERNAME),
+            "openid.lp.is_member": "packager",
+            "openid.mode": "id_res",
+            "openid.ns": "http://specs.openid.net/auth/2.0",
+            "openid.ns.cla": "http://fedoraproject.org/specs/open_id/cla",
+            "openid.ns.lp": "http://ns.launchpad.net/2007/openid-teams",
+            "openid.ns.sreg": "http://openid.net/extensions/sreg/1.1",
+            "openid.op_endpoint": format!("{}/openid/", uri),
+            "openid.response_nonce": "2024-05-06T10:00:00Zstandin",
+            "openid.return_to": format!("{}/dologin.html", uri),
+            "openid.sig": "standin",
+            "openid.signed": "assoc_handle,identity,mode,ns,op_endpoint,response_nonce,return_to,signed",
+            "openid.sreg.email": format!("{}@fedoraproject.org", USERNAME),
+            "openid.sreg.nickname": USERNAME,
+        }
+    })
+}
@@ -0,0 +1,971 @@
+//! These tests run the full `fedora-update-feedback` flow against a local stand-in for bodhi.
+
+#![allow(clippy::unwrap_used)]
+
+mod common;
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use common::{json_fixture, stderr, stdout, StandIn};
+
+const BATCH: &str = r#"
+[[feedback]]
+update = "FEDORA-2024-0001aaaa"
+comment = "Works fine."
+karma = 1
+
+[[feedback]]
+package = "qux"
+karma = 1
+"#;
+
+#[tokio::test]
+async fn batch_submits_feedback() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    let batch = stand_in.write_file("batch.toml", BATCH);
+
+    let output = stand_in.run(&["--batch", batch.to_str().unwrap()], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+
+    // karma was reset after the previous comment for FEDORA-2024-0001aaaa, but not for
+    // FEDORA-2024-0003cccc, so only one comment is submitted
+    assert!(stdout.contains("FEDORA-2024-0003cccc: A comment was already submitted, skipping."));
+    assert!(stdout.contains("Submitted: 1, Skipped: 1, Failed: 0"));
+
+    // caveats returned by bodhi are printed
+    assert!(stdout.contains("Your karma standing was reversed."));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["update"], "FEDORA-2024-0001aaaa");
+    assert_eq!(comments[0]["text"], "Works fine.");
+    assert_eq!(comments[0]["karma"], 1);
+    assert_eq!(comments[0]["csrf_token"], "standin");
+
+    // the user was authenticated with the OpenID stand-in
+    assert_eq!(stand_in.requests("POST", "/openid/").await.len(), 1);
+}
+
+#[tokio::test]
+async fn bodhi_error_keeps_feedback_in_queue() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(400, json_fixture("bodhi/error.json"))
+        .await;
+    let batch = stand_in.write_file("batch.toml", BATCH);
+
+    let output = stand_in.run(&["--batch", batch.to_str().unwrap()], "").await;
+    let stdout = stdout(&output);
+
+    // failed requests to bodhi have a distinct exit code
+    assert_eq!(output.status.code(), Some(69));
+    assert!(stderr(&output).contains("Error: Failed to provide 1 feedback item."));
+    assert!(stderr(&output).contains("Feedback that could not be submitted is kept in the local queue."));
+
+    // the error message from bodhi is shown, and the feedback is not lost
+    assert!(stdout.contains("Cannot comment on an update that is locked."));
+    assert!(stdout.contains("Submitted: 0, Skipped: 1, Failed: 1"));
+
+    let queue = stand_in.queue();
+    assert_eq!(queue["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(queue["entries"][0]["alias"], "FEDORA-2024-0001aaaa");
+    assert_eq!(queue["entries"][0]["comment"], "Works fine.");
+}
+
+#[tokio::test]
+async fn batch_entry_not_installed() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    let batch = stand_in.write_file(
+        "batch.toml",
+        &format!("{}\n[[feedback]]\npackage = \"missing\"\nkarma = 1\n", BATCH),
+    );
+
+    let output = stand_in.run(&["--batch", batch.to_str().unwrap()], "").await;
+    let stdout = stdout(&output);
+    let stderr = stderr(&output);
+
+    // entries that do not match installed updates are a problem with the batch file, not with bodhi
+    assert_eq!(output.status.code(), Some(65), "{}", stderr);
+    assert!(
+        stdout.contains("package 'missing': No matching update is installed."),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("Submitted: 1, Skipped: 1, Failed: 0"));
+    assert!(stdout.contains("Not installed: 1"));
+    assert!(stderr.contains("Error: No installed update matches 1 feedback item."));
+    assert!(!stderr.contains("network connection"));
+
+    // feedback for other entries is still submitted
+    assert_eq!(stand_in.submitted_comments().await.len(), 1);
+}
+
+#[tokio::test]
+async fn batch_bug_feedback_only() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    let batch = stand_in.write_file(
+        "batch.toml",
+        "[[feedback]]\nupdate = \"FEDORA-2024-0001aaaa\"\nbugs = { \"2000001\" = 1 }\n",
+    );
+
+    // entries with only bug feedback are neither rejected nor skipped
+    let output = stand_in.run(&["--batch", batch.to_str().unwrap()], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+    assert!(stdout.contains("Submitted: 1, Skipped: 0, Failed: 0"));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["karma"], 0);
+    assert_eq!(comments[0]["bug_feedback.0.bug_id"], "2000001");
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "1");
+}
+
+#[tokio::test]
+async fn retry_failed_submissions() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_next_comment(400, json_fixture("bodhi/error.json"))
+        .await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // the first request fails, and is retried at the end of the session
+    let editor = stand_in.write_editor("Works fine.");
+    let output = stand_in
+        .run_with_env(&[], "c\n+1\n1\n\ny\n", &[("EDITOR", editor.as_path())])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout.contains("Cannot comment on an update that is locked."));
+    assert!(stdout.contains("Retrying in 1 second (attempt 1 of 3) ..."));
+    assert!(stdout.contains("FEDORA-2024-0001aaaa: Comment created."));
+    assert!(stdout.contains("  Updates reviewed:  1\n"));
+    assert!(stdout.contains("  Comments posted:   1 (karma: 1 × +1)\n"));
+
+    assert_eq!(stand_in.submitted_comments().await.len(), 2);
+    assert!(stand_in.queue()["entries"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn failed_submissions_are_queued() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(400, json_fixture("bodhi/error.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // feedback is saved in the local queue if retrying is declined
+    let editor = stand_in.write_editor("Works fine.");
+    let output = stand_in
+        .run_with_env(&[], "c\n+1\n1\n\nn\n", &[("EDITOR", editor.as_path())])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout.contains("Retrying"));
+    assert!(stdout.contains("  Comments posted:   0\n"));
+    assert!(stdout.contains("  Failed:            1 (saved in the local queue)"));
+
+    assert_eq!(stand_in.submitted_comments().await.len(), 1);
+    assert_eq!(stand_in.queue()["entries"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn queue_and_submit_later() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    let batch = stand_in.write_file("batch.toml", BATCH);
+
+    let output = stand_in.run(&["--queue", "--batch", batch.to_str().unwrap()], "").await;
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Queued: 1, Skipped: 1, Failed: 0"));
+
+    // feedback was only saved locally, without authenticating
+    assert!(stand_in.requests("POST", "/openid/").await.is_empty());
+    assert!(stand_in.submitted_comments().await.is_empty());
+    assert_eq!(stand_in.queue()["entries"].as_array().unwrap().len(), 1);
+
+    let output = stand_in.run(&["submit"], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("[1/1] FEDORA-2024-0001aaaa: foo-1.2-1.fc40"));
+    assert!(stdout.contains("Submitted: 1, Failed: 0"));
+
+    assert_eq!(stand_in.submitted_comments().await.len(), 1);
+    assert!(stand_in.queue()["entries"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn interrupted_submit_keeps_queue_consistent() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_next_comment(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.stall_comments().await;
+    stand_in.set_queue(json!({
+        "entries": [
+            {
+                "alias": "FEDORA-2024-0001aaaa",
+                "title": "foo-1.2-1.fc40",
+                "recorded": "2024-05-06T10:00:00Z",
+                "comment": "Works fine.",
+                "karma": 1,
+                "bug_feedback": [],
+                "testcase_feedback": [],
+            },
+            {
+                "alias": "FEDORA-2024-0002bbbb",
+                "title": "bar-2.0-1.fc40",
+                "recorded": "2024-05-06T10:05:00Z",
+                "comment": "Works for me.",
+                "karma": 1,
+                "bug_feedback": [],
+                "testcase_feedback": [],
+            },
+        ]
+    }));
+
+    // the program is killed while the second entry is being submitted
+    let mut child = stand_in.spawn(&["submit"]);
+    let started = Instant::now();
+    while stand_in.submitted_comments().await.len() < 2 {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "second comment was not submitted"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    child.kill().await.unwrap();
+
+    // the entry that was already submitted is no longer in the queue
+    let queue = stand_in.queue();
+    let entries = queue["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["alias"], "FEDORA-2024-0002bbbb");
+}
+
+#[tokio::test]
+async fn interactive_comment() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+    let editor = stand_in.write_editor("Works fine.");
+
+    // comment on the only remaining update, with positive karma, positive bug feedback, and no
+    // test case feedback
+    let output = stand_in
+        .run_with_env(&[], "c\n+1\n1\n\n", &[("EDITOR", editor.as_path())])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+
+    // printed links point at the stand-in server
+    assert!(stdout.contains(&format!(
+        "URL:            {}/updates/FEDORA-2024-0001aaaa",
+        stand_in.server.uri()
+    )));
+    assert!(stdout.contains("Comment created."));
+
+    // new changelog entries of installed builds are shown
+    assert!(stdout.contains(
+        "Changelog of foo-1.2-1.fc40 (new since 1.1-1):\n* Mon May 06 2024 Jane Doe <jane@example.com> - 1.2-1\n  - Update to version 1.2\n"
+    ));
+    assert!(!stdout.contains("- Update to version 1.1"));
+
+    // previous karma was given for builds that have since been replaced
+    assert!(stdout.contains("Changes since your last comment:\n- replaced build: foo-1.1-1.fc40 -> foo-1.2-1.fc40"));
+
+    // the state of the update is remembered for showing changes later
+    let snapshots: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(stand_in.home_path(".local/share/fedora-update-feedback.snapshots")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(snapshots["FEDORA-2024-0001aaaa"]["builds"], json!(["foo-1.2-1.fc40"]));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["update"], "FEDORA-2024-0001aaaa");
+    assert_eq!(comments[0]["text"], "Works fine.\n");
+    assert_eq!(comments[0]["karma"], 1);
+    assert_eq!(comments[0]["bug_feedback.0.bug_id"], "2000001");
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "1");
+}
+
+#[tokio::test]
+async fn partial_update_warning() {
+    // add a build that is not installed to the update for "foo"
+    let mut updates = json_fixture("updates.json");
+    let mut extra = updates[0]["builds"][0].clone();
+    extra["nvr"] = json!("foo-extras-1.2-1.fc40");
+    updates[0]["builds"].as_array_mut().unwrap().push(extra);
+
+    let stand_in = StandIn::with_testing(updates).await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    let output = stand_in.run(&[], "s\n").await;
+    let printed = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", printed, stderr(&output));
+    assert!(printed.contains(
+        "WARNING: Only 1 of 2 builds from this update are installed:\n- foo-extras-1.2-1.fc40 (not installed)\n"
+    ));
+    assert!(printed.contains(
+        "  (mkdir foo-extras-1.2-1.fc40 && cd foo-extras-1.2-1.fc40 && \
+         koji download-build --arch=noarch --arch=$(uname -m) foo-extras-1.2-1.fc40 && sudo dnf install ./*.rpm)\n"
+    ));
+
+    // partially installed updates can be skipped entirely
+    let output = stand_in.run(&["review", "--skip-partial"], "").await;
+    let printed = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", printed, stderr(&output));
+    assert!(printed.contains("Skipping 1 update where only some builds are installed."));
+    assert!(!printed.contains("WARNING: Only"));
+
+    let output = stand_in.run(&["list", "--format", "json"], "").await;
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entries[0]["missing_builds"], json!(["foo-extras-1.2-1.fc40"]));
+}
+
+#[tokio::test]
+async fn restart_warning() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // a running process still uses the library from the previous version of foo-libs
+    let mut inventory = json_fixture("inventory.json");
+    inventory["running"] = json!({
+        "boot-time": "2024-05-01T08:00:00Z",
+        "kernel": "6.8.9-300.fc40.x86_64",
+        "deleted-files": { "/usr/lib64/libfoo.so.1.1.0": ["foo (1234)"] },
+    });
+    inventory["files"] = json!({ "foo-libs-1.2-1.fc40.x86_64": ["/usr/lib64/libfoo.so.1.2.0"] });
+    stand_in.set_inventory(inventory);
+
+    let editor = stand_in.write_editor("Works fine.");
+
+    // positive karma is not submitted without confirmation
+    let output = stand_in
+        .run_with_env(&[], "c\n+1\nn\n1\n\n", &[("EDITOR", editor.as_path())])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+    assert!(stdout
+        .contains("- Running processes still use files from before foo-1.2-1.fc40 was installed:\n  foo (1234)\n"));
+    assert!(stdout.contains("Not submitting karma for this update."));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Works fine.\n");
+    assert_eq!(comments[0]["karma"], 0);
+}
+
+#[tokio::test]
+async fn skip_running_check() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+    stand_in.append_config("check-running = false");
+
+    let mut inventory = json_fixture("inventory.json");
+    inventory["running"] = json!({
+        "boot-time": "2024-05-01T08:00:00Z",
+        "kernel": "6.8.9-300.fc40.x86_64",
+        "deleted-files": { "/usr/lib64/libfoo.so.1.1.0": ["foo (1234)"] },
+    });
+    inventory["files"] = json!({ "foo-libs-1.2-1.fc40.x86_64": ["/usr/lib64/libfoo.so.1.2.0"] });
+    stand_in.set_inventory(inventory);
+
+    let editor = stand_in.write_editor("Works fine.");
+
+    // the running system is not inspected, so positive karma does not need to be confirmed
+    let output = stand_in
+        .run_with_env(&[], "c\n+1\n1\n\n", &[("EDITOR", editor.as_path())])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+    assert!(!stdout.contains("Running processes still use files"));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["karma"], 1);
+}
+
+#[tokio::test]
+async fn soak_time() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+    stand_in.append_config("\n[fedora-update-feedback.soak-time]\nstrict = true\npackages = { foo = 100000 }");
+    let editor = stand_in.write_editor("Works fine.");
+
+    // positive karma is refused for updates that were installed too recently
+    let output = stand_in
+        .run_with_env(&[], "c\n+1\n1\n\n", &[("EDITOR", editor.as_path())])
+        .await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+    assert!(stdout.contains("positive karma should only be provided after 100000 days."));
+    assert!(stdout.contains("Not submitting karma for this update."));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["karma"], 0);
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "1");
+}
+
+#[tokio::test]
+async fn installed_days() {
+    let stand_in = StandIn::start().await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // all updates in the canned inventory were installed more than a day ago
+    let output = stand_in.run(&["review", "--installed-max-days", "1"], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+    assert!(stdout.contains("Skipping 2 updates outside of the selected range of installation times."));
+    assert!(!stdout.contains("FEDORA-2024-0001aaaa"));
+}
+
+#[tokio::test]
+async fn comment_template() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+    stand_in.append_config(
+        "\n[fedora-update-feedback.templates.works-for-me]\n\
+         text = \"Works for me: {builds} on {release}.\"\n\
+         karma = 1",
+    );
+
+    // pick the first template, submit it without editing, and provide bug feedback
+    let output = stand_in.run(&[], "t\n1\n\n1\n\n").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+    assert!(stdout.contains("[1] works-for-me: Works for me: foo-1.2-1.fc40 on F40."));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Works for me: foo-1.2-1.fc40 on F40.");
+    assert_eq!(comments[0]["karma"], 1);
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "1");
+}
+
+#[tokio::test]
+async fn editor_fields() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // fill in the comment and feedback fields in the pre-filled buffer, no further prompts
+    let editor = stand_in.write_editor_script(
+        "sed -i -e '1s/^$/Works fine./' -e 's/^karma:$/karma: +1/' -e 's/^bug 2000001:$/bug 2000001: -1/' \"$1\"",
+    );
+    let output = stand_in.run_with_env(&[], "c\n", &[("EDITOR", editor.as_path())]).await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout(&output).contains("Bug Feedback"));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Works fine.\n");
+    assert_eq!(comments[0]["karma"], 1);
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "-1");
+}
+
+#[tokio::test]
+async fn bug_feedback_only() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // feedback for a bug without a comment or karma is still submitted
+    let editor = stand_in.write_editor_script("sed -i -e 's/^bug 2000001:$/bug 2000001: +1/' \"$1\"");
+    let output = stand_in.run_with_env(&[], "c\n", &[("EDITOR", editor.as_path())]).await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout.contains("skipping"), "{}", stdout);
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["karma"], 0);
+    assert_eq!(comments[0]["bug_feedback.0.bug_id"], "2000001");
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "1");
+}
+
+#[tokio::test]
+async fn editor_command() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // VISUAL takes precedence over EDITOR, and can contain arguments
+    let editor = stand_in.write_editor_script("[ \"$1\" = \"--wait\" ] || exit 1\nprintf 'Works fine.\\n' > \"$2\"");
+    let visual = format!("{} --wait", editor.display());
+    let output = stand_in
+        .run_with_env(
+            &[],
+            "c\n+1\n1\n\n",
+            &[("VISUAL", Path::new(&visual)), ("EDITOR", Path::new("false"))],
+        )
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Works fine.\n");
+}
+
+#[tokio::test]
+async fn draft_recovery() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // the draft is kept if the editor crashes
+    let editor = stand_in.write_editor_script("sed -i '1s/^$/Long write-up./' \"$1\"\nexit 1");
+    let output = stand_in.run_with_env(&[], "c\n", &[("EDITOR", editor.as_path())]).await;
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("The draft comment was kept in"));
+    assert!(stand_in.submitted_comments().await.is_empty());
+
+    let draft = stand_in.home_path(".local/share/fedora-update-feedback.drafts/FEDORA-2024-0001aaaa.md");
+    assert!(std::fs::read_to_string(&draft).unwrap().starts_with("Long write-up.\n"));
+
+    // the draft is restored in the next session by default, and removed after submitting feedback
+    let output = stand_in
+        .run_with_env(&[], "c\n\n", &[("EDITOR", Path::new("true"))])
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("There is a draft comment for this update from a previous session."));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Long write-up.\n");
+    assert!(!draft.exists());
+}
+
+#[tokio::test]
+async fn draft_discarded_for_template() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+    stand_in.append_config(
+        "\n[fedora-update-feedback.templates.works-for-me]\n\
+         text = \"Works for me: {builds} on {release}.\"\n\
+         karma = 1",
+    );
+
+    let draft = stand_in.home_path(".local/share/fedora-update-feedback.drafts/FEDORA-2024-0001aaaa.md");
+    std::fs::create_dir_all(draft.parent().unwrap()).unwrap();
+    std::fs::write(&draft, "Old write-up.\n").unwrap();
+
+    // the draft does not silently replace the template if it is discarded
+    let output = stand_in
+        .run_with_env(&[], "t\n1\ne\nd\n", &[("EDITOR", Path::new("true"))])
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("There is a draft comment for this update from a previous session."));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Works for me: foo-1.2-1.fc40 on F40.\n");
+    assert_eq!(comments[0]["karma"], 1);
+    assert!(!draft.exists());
+}
+
+#[tokio::test]
+async fn inline_editor() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+    stand_in.append_config("inline-editor = true");
+
+    // the comment is entered in the terminal and ends with two empty lines
+    let output = stand_in
+        .run(&[], "comment\nWorks fine.\nNo regressions.\n\n\n+1\n1\n\n")
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0]["text"], "Works fine.\nNo regressions.\n");
+    assert_eq!(comments[0]["karma"], 1);
+    assert_eq!(comments[0]["bug_feedback.0.karma"], "1");
+
+    assert!(stdout(&output).contains("[c] comment with feedback (in the terminal)"));
+
+    // answers to prompts are saved in the prompt history, but comments are not
+    let history = std::fs::read_to_string(stand_in.home_path(".local/share/fedora-update-feedback.history")).unwrap();
+    assert!(history.lines().any(|line| line == "comment"), "{}", history);
+    assert!(!history.contains("Works fine."), "{}", history);
+}
+
+#[tokio::test]
+async fn system_info_footer() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    let mut inventory = json_fixture("inventory.json");
+    inventory["running"] = json!({
+        "kernel": "6.8.9-300.fc40.x86_64",
+        "hardware": "LENOVO 21AH00BSGE",
+    });
+    stand_in.set_inventory(inventory);
+
+    let editor = stand_in.write_editor("Works fine.");
+    let output = stand_in
+        .run_with_env(
+            &["review", "--system-info"],
+            "c\n+1\n1\n\n",
+            &[
+                ("EDITOR", editor.as_path()),
+                ("XDG_CURRENT_DESKTOP", Path::new("GNOME")),
+                ("XDG_SESSION_TYPE", Path::new("wayland")),
+            ],
+        )
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    assert_eq!(
+        comments[0]["text"],
+        format!(
+            "Works fine.\n\n---\n\
+             System: F40 ({}), kernel 6.8.9-300.fc40.x86_64, GNOME (wayland), LENOVO 21AH00BSGE\n\
+             Installed: foo-1.2-1.fc40.x86_64, foo-libs-1.2-1.fc40.x86_64\n",
+            std::env::consts::ARCH
+        )
+    );
+}
+
+#[tokio::test]
+async fn system_info_without_running_check() {
+    let stand_in = StandIn::start().await;
+    stand_in
+        .respond_to_comments(200, json_fixture("bodhi/comment.json"))
+        .await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    let mut inventory = json_fixture("inventory.json");
+    inventory["running"] = json!({
+        "kernel": "6.8.9-300.fc40.x86_64",
+        "hardware": "LENOVO 21AH00BSGE",
+    });
+    stand_in.set_inventory(inventory);
+
+    // the kernel and hardware model are still included if running processes are not inspected
+    let editor = stand_in.write_editor("Works fine.");
+    let output = stand_in
+        .run_with_env(
+            &["review", "--system-info", "--skip-running-check"],
+            "c\n+1\n1\n\n",
+            &[("EDITOR", editor.as_path())],
+        )
+        .await;
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let comments = stand_in.submitted_comments().await;
+    assert_eq!(comments.len(), 1);
+    let text = comments[0]["text"].as_str().unwrap();
+    assert!(text.contains("kernel 6.8.9-300.fc40.x86_64"), "{}", text);
+    assert!(text.contains("LENOVO 21AH00BSGE"), "{}", text);
+}
+
+#[tokio::test]
+async fn ignore_list_is_pruned() {
+    let stand_in = StandIn::start().await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2023-00000000", "FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    // ignore the only remaining update
+    let output = stand_in.run(&[], "i\n").await;
+    assert!(output.status.success(), "{}\n{}", stdout(&output), stderr(&output));
+
+    // updates that are no longer installed are removed, and new ones are added
+    assert_eq!(
+        stand_in.ignored(),
+        json!({
+            "ignored_updates": ["FEDORA-2024-0001aaaa", "FEDORA-2024-0006ffff"],
+            "ignored_packages": ["blocked"],
+        })
+    );
+    assert!(stand_in.submitted_comments().await.is_empty());
+}
+
+#[tokio::test]
+async fn obsoleted_and_unpushed_checks() {
+    let stand_in = StandIn::with_testing(json!([])).await;
+
+    let output = stand_in.run(&["--check-obsoleted", "--check-unpushed"], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+
+    assert!(stdout.contains("No updates that are waiting for feedback are currently installed."));
+
+    assert!(stdout.contains("There are obsoleted updates installed on this system."));
+    assert!(stdout.contains(" - notinstalled-0.9-1.fc40:\n   - notinstalled-0.9-1.fc40.noarch"));
+
+    assert!(stdout.contains("There are unpushed updates installed on this system."));
+    assert!(stdout.contains(" - stale-0.5-1.fc40:\n   - stale-0.5-1.fc40.noarch"));
+}
+
+#[tokio::test]
+async fn checks_are_prefetched() {
+    let stand_in = StandIn::start().await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    let output = stand_in.run(&["--check-pending", "--check-unpushed"], "s\n").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+
+    // "testing" and "pending" updates are queried at startup, and "unpushed" updates while
+    // reviewing, but the results of the check are only reported at the end
+    let queried: Vec<String> = stand_in
+        .requests("GET", "/updates/")
+        .await
+        .iter()
+        .filter_map(|request| {
+            request
+                .url
+                .query_pairs()
+                .find(|(key, _)| key == "status")
+                .map(|(_, value)| value.to_string())
+        })
+        .collect();
+    assert_eq!(queried.len(), 3);
+    for status in ["testing", "pending", "unpushed"] {
+        assert!(queried.iter().any(|queried| queried == status), "{:?}", queried);
+    }
+
+    let summary = stdout.find("Summary:").unwrap();
+    let unpushed = stdout
+        .find("There are unpushed updates installed on this system.")
+        .unwrap();
+    assert!(summary < unpushed);
+}
+
+#[tokio::test]
+async fn invalid_bodhi_url() {
+    let stand_in = StandIn::start().await;
+
+    let output = stand_in.run(&["--bodhi-url", "bodhi"], "").await;
+    let stderr = stderr(&output);
+
+    // configuration errors have a distinct exit code, and are reported with their cause and a hint
+    assert_eq!(output.status.code(), Some(78));
+    assert!(stderr.contains("Error: Invalid bodhi URL (bodhi)\n  Caused by: relative URL without a base\n"));
+    assert!(stderr.contains("Hint: Check the [FAS] and [fedora-update-feedback] sections"));
+}
+
+#[tokio::test]
+async fn invalid_config_file() {
+    let stand_in = StandIn::start().await;
+    stand_in.append_config("check-pending = maybe");
+
+    let output = stand_in.run(&["list"], "").await;
+    let stderr = stderr(&output);
+
+    // a configuration file that cannot be parsed is reported instead of being ignored
+    assert_eq!(output.status.code(), Some(78), "{}", stderr);
+    assert!(stderr.contains("Error: Unable to parse configuration file from ~/.config/fedora.toml\n  Caused by: "));
+    assert!(stand_in.requests("GET", "/updates/").await.is_empty());
+}
+
+#[tokio::test]
+async fn legacy_submit_queued() {
+    let stand_in = StandIn::start().await;
+
+    // flags from before subcommands were introduced still work
+    let output = stand_in.run(&["--submit-queued"], "").await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("No feedback is waiting in the local queue."));
+}
+
+#[tokio::test]
+async fn list_does_not_prompt() {
+    let stand_in = StandIn::start().await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    let output = stand_in.run(&["list"], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+
+    assert!(stdout.contains("ALIAS "));
+    let rows: Vec<&str> = stdout.lines().filter(|line| line.starts_with("FEDORA-")).collect();
+    assert_eq!(rows.len(), 1, "{}", stdout);
+    assert!(rows[0].contains("foo-1.2-1.fc40"));
+    assert!(rows[0].starts_with("FEDORA-2024-0001aaaa"));
+
+    // listing updates does not require authentication or modify the list of ignored updates
+    assert!(stand_in.requests("POST", "/openid/").await.is_empty());
+    assert_eq!(stand_in.ignored()["ignored_updates"], json!(["FEDORA-2024-0006ffff"]));
+}
+
+#[tokio::test]
+async fn list_as_json() {
+    let stand_in = StandIn::start().await;
+    stand_in.set_ignored(json!({
+        "ignored_updates": ["FEDORA-2024-0006ffff"],
+        "ignored_packages": ["blocked"],
+    }));
+
+    let output = stand_in.run(&["list", "--format", "json", "--check-ignored"], "").await;
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // stdout only contains the JSON document, even if status messages are printed
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let aliases: Vec<&str> = entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["alias"].as_str().unwrap())
+        .collect();
+    assert_eq!(aliases, vec!["FEDORA-2024-0006ffff", "FEDORA-2024-0001aaaa"]);
+
+    let ignored = &entries[0];
+    assert_eq!(ignored["ignored"], json!(true));
+    assert_eq!(ignored["feedback"], json!("never_commented"));
+
+    let foo = &entries[1];
+    assert_eq!(foo["title"], json!("foo-1.2-1.fc40"));
+    assert_eq!(foo["builds"], json!(["foo-1.2-1.fc40"]));
+    assert_eq!(foo["ignored"], json!(false));
+    assert_eq!(foo["feedback"], json!("karma_for_superseded_builds"));
+    assert!(foo["type"].is_string());
+    assert!(foo.get("stable_karma").is_some());
+    assert!(foo.get("installed").is_some());
+}
+
+#[tokio::test]
+async fn ignore_and_status() {
+    let stand_in = StandIn::start().await;
+
+    let output = stand_in.run(&["ignore", "add", "foo"], "").await;
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Added 'foo' to the list of ignored packages."));
+
+    let output = stand_in.run(&["ignore", "list"], "").await;
+    assert!(stdout(&output).contains("Ignored packages:\n- foo\n"));
+
+    let output = stand_in.run(&["status"], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout.contains("Username:         tester"));
+    assert!(stdout.contains(&format!("Bodhi instance:   {}", stand_in.server.uri())));
+    assert!(stdout.contains("Queued feedback:  0 updates"));
+    assert!(stdout.contains("Ignored packages: 1 package"));
+}
+
+#[tokio::test]
+async fn check_subcommand() {
+    let stand_in = StandIn::start().await;
+
+    let output = stand_in.run(&["check", "unpushed"], "").await;
+    let stdout = stdout(&output);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr(&output));
+
+    assert!(stdout.contains("There are unpushed updates installed on this system."));
+    assert!(!stdout.contains("obsoleted"));
+    assert!(stand_in.requests("POST", "/openid/").await.is_empty());
+}